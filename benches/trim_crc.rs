@@ -0,0 +1,52 @@
+#![feature(test)]
+
+extern crate test;
+
+use crc::{Crc, CRC_16_EN_13757};
+use test::{black_box, Bencher};
+use wmbus::stack::phl::{self, FrameFormat, FFA, FFB};
+
+const CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_EN_13757);
+
+fn ffa_frame() -> ([u8; phl::FRAME_MAX], usize) {
+    let mut frame = [0; phl::FRAME_MAX];
+    let mut writer = &mut frame[..];
+    phl::write_ffa_frame(&mut writer, &[0x55; FFA::DATA_MAX - 1]).unwrap();
+    let frame_length = phl::FRAME_MAX - writer.len();
+    (frame, frame_length)
+}
+
+fn ffb_frame() -> [u8; FFB::FRAME_MAX] {
+    let mut frame = [0x55; FFB::FRAME_MAX];
+    frame[0] = (FFB::FRAME_MAX - 1) as u8;
+    // The first and second block share a single CRC, and the optional block has its own CRC
+    for block in frame.chunks_mut(128) {
+        let crc = CRC.checksum(&block[..126]).to_be_bytes();
+        block[126..].copy_from_slice(&crc);
+    }
+    frame
+}
+
+#[bench]
+fn ffa_trim_crc(b: &mut Bencher) {
+    let (frame, frame_length) = ffa_frame();
+    b.iter(|| FFA::trim_crc(black_box(&frame[..frame_length])).unwrap());
+}
+
+#[bench]
+fn ffa_trim_crc_no_verify(b: &mut Bencher) {
+    let (frame, frame_length) = ffa_frame();
+    b.iter(|| FFA::trim_crc_no_verify(black_box(&frame[..frame_length])).unwrap());
+}
+
+#[bench]
+fn ffb_trim_crc(b: &mut Bencher) {
+    let frame = ffb_frame();
+    b.iter(|| FFB::trim_crc(black_box(&frame)).unwrap());
+}
+
+#[bench]
+fn ffb_trim_crc_no_verify(b: &mut Bencher) {
+    let frame = ffb_frame();
+    b.iter(|| FFB::trim_crc_no_verify(black_box(&frame)).unwrap());
+}
@@ -16,15 +16,15 @@ fn main() {
     packet.apl.extend_from_slice(&apl).unwrap();
 
     for i in 0..100 {
-        packet.dll = Some(DllFields {
-            control: 0x44,
-            address: WMBusAddress::new(
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(
                 ManufacturerCode::KAM,
                 12345600 + i,
                 0x01,
                 DeviceType::Repeater,
             ),
-        });
+        ));
 
         let mut writer = BytesMut::new();
         writer.put_slice(&[0x55, 0x55, 0x55, 0x55]);
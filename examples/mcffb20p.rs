@@ -8,10 +8,10 @@ use wmbus::{
 fn main() {
     let stack = Stack::new();
     let mut packet: Packet = Packet::new(Mode::ModeCFFB);
-    packet.dll = Some(DllFields {
-        control: 0x44,
-        address: WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
-    });
+    packet.dll = Some(DllFields::new(
+        0x44,
+        WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+    ));
     packet
         .apl
         .extend_from_slice(&[0xa0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08])
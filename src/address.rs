@@ -1,5 +1,6 @@
-use core::fmt::Display;
+use core::fmt::{Display, Write};
 
+use heapless::String;
 use nobcd::{BcdError, BcdNumber};
 
 use crate::{DeviceType, ManufacturerCode};
@@ -12,22 +13,41 @@ pub struct WMBusAddress {
     pub device_type: u8,
 }
 
+/// An address with the manufacturer letters and the serial number formatted once, see [`WMBusAddress::resolve()`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedAddress {
+    pub address: WMBusAddress,
+    manufacturer: Option<String<3>>,
+    serial_number: String<8>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum WMBusAddressError {
     SerialNumberBcd,
+    /// The manufacturer is not exactly three uppercase letters
+    ManufacturerString,
 }
 
 enum FieldLayout {
     Default, // The default layout according to EN13757, i.e. Manufacturer, serial number, version, type
+    #[cfg_attr(feature = "strict-layout", allow(dead_code))]
     Diehl, // The layout used by Diehl on some of its meters, i.e. Manufacturer, version, type, serial number
 }
 
 impl Display for WMBusAddress {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.manufacturer_string() {
+            Some(letters) => {
+                for letter in letters {
+                    write!(f, "{}", letter as char)?;
+                }
+            }
+            None => write!(f, "{:#x}", self.manufacturer_code)?,
+        }
         write!(
             f,
-            "{:#x}:{:?}/{:?}/{:?}",
-            self.manufacturer_code, self.serial_number, self.version, self.device_type
+            ":{:?}/{:?}/{:?}",
+            self.serial_number, self.version, self.device_type
         )
     }
 }
@@ -40,13 +60,52 @@ impl WMBusAddress {
         device_type: DeviceType,
     ) -> Self {
         Self {
-            manufacturer_code: manufacturer_code as u16,
+            manufacturer_code: manufacturer_code.code(),
             serial_number: BcdNumber::new(serial_number).unwrap(),
             version,
             device_type: device_type as u8,
         }
     }
 
+    /// Create an address with a three letter manufacturer code, e.g. `"KAM"`, see [`WMBusAddress::manufacturer_string()`]
+    pub fn with_manufacturer_str(
+        manufacturer: &str,
+        serial_number: u32,
+        version: u8,
+        device_type: DeviceType,
+    ) -> Result<Self, WMBusAddressError> {
+        let manufacturer_code = manufacturer
+            .as_bytes()
+            .try_into()
+            .ok()
+            .and_then(ManufacturerCode::code_from_letters)
+            .ok_or(WMBusAddressError::ManufacturerString)?;
+
+        Ok(Self {
+            manufacturer_code,
+            serial_number: BcdNumber::new(serial_number)
+                .map_err(|_| WMBusAddressError::SerialNumberBcd)?,
+            version,
+            device_type: device_type as u8,
+        })
+    }
+
+    /// Create an address from its raw fields.
+    /// Unlike [`WMBusAddress::new()`] this accepts any manufacturer code and device type, and cannot panic.
+    pub const fn from_parts(
+        manufacturer_code: u16,
+        serial_number: BcdNumber<4>,
+        version: u8,
+        device_type: u8,
+    ) -> Self {
+        Self {
+            manufacturer_code,
+            serial_number,
+            version,
+            device_type,
+        }
+    }
+
     pub fn from_bytes(value: [u8; 8]) -> Result<WMBusAddress, WMBusAddressError> {
         let layout = get_layout(&value);
         match layout {
@@ -67,8 +126,14 @@ impl WMBusAddress {
         }
     }
 
-    pub fn manufacturer_code(&self) -> Option<ManufacturerCode> {
-        self.manufacturer_code.try_into().ok()
+    /// Get the manufacturer code, where unknown manufacturers are [`ManufacturerCode::Other`]
+    pub fn manufacturer_code(&self) -> ManufacturerCode {
+        self.manufacturer_code.into()
+    }
+
+    /// Get the three letter manufacturer code, e.g. `b"KAM"`, see [`ManufacturerCode::letters()`]
+    pub const fn manufacturer_string(&self) -> Option<[u8; 3]> {
+        ManufacturerCode::Other(self.manufacturer_code).letters()
     }
 
     pub fn serial_number(&self) -> u32 {
@@ -83,6 +148,20 @@ impl WMBusAddress {
         self.device_type.try_into().ok()
     }
 
+    /// Resolve the manufacturer letters and the zero padded eight digit serial number for repeated display
+    pub fn resolve(&self) -> ResolvedAddress {
+        let manufacturer = self
+            .manufacturer_string()
+            .map(|letters| letters.iter().map(|&letter| letter as char).collect());
+        let mut serial_number = String::new();
+        write!(serial_number, "{:08}", self.serial_number()).unwrap();
+        ResolvedAddress {
+            address: self.clone(),
+            manufacturer,
+            serial_number,
+        }
+    }
+
     pub fn get_bytes(&self) -> [u8; 8] {
         let mut bytes = [0; 8];
         bytes[0..2].copy_from_slice(self.manufacturer_code.to_le_bytes().as_ref());
@@ -101,6 +180,18 @@ impl WMBusAddress {
     }
 }
 
+impl ResolvedAddress {
+    /// Get the three letter manufacturer code, e.g. `"KAM"`, see [`WMBusAddress::manufacturer_string()`]
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.manufacturer.as_deref()
+    }
+
+    /// Get the eight digit serial number, e.g. `"01234567"`
+    pub fn serial_number(&self) -> &str {
+        &self.serial_number
+    }
+}
+
 impl TryFrom<&[u8; 8]> for WMBusAddress {
     type Error = WMBusAddressError;
 
@@ -109,9 +200,16 @@ impl TryFrom<&[u8; 8]> for WMBusAddress {
     }
 }
 
+#[cfg(feature = "strict-layout")]
+fn get_layout(_value: &[u8; 8]) -> FieldLayout {
+    // Manufacturer specific layouts are disabled for strict EN13757 parsing
+    FieldLayout::Default
+}
+
+#[cfg(not(feature = "strict-layout"))]
 fn get_layout(value: &[u8; 8]) -> FieldLayout {
     let manufacturer_code = u16::from_le_bytes(value[0..2].try_into().unwrap());
-    if manufacturer_code == ManufacturerCode::HYD as u16 {
+    if manufacturer_code == ManufacturerCode::HYD.code() {
         // These indexes are not correct according to the standard, but are used by Diehl
         let version = value[2];
         let device_type = value[3];
@@ -140,7 +238,7 @@ fn get_layout(value: &[u8; 8]) -> FieldLayout {
         } else if device_type == 0x16 && version == 0x25 {
             return FieldLayout::Diehl;
         }
-    } else if manufacturer_code == ManufacturerCode::DME as u16 {
+    } else if manufacturer_code == ManufacturerCode::DME.code() {
         // These indexes are not correct according to the standard, but are used by Diehl
         let version = value[2];
         let device_type = value[3];
@@ -168,17 +266,186 @@ pub mod tests {
     pub fn parse_default() {
         let address =
             WMBusAddress::from_bytes([0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32]).unwrap();
-        assert_eq!(ManufacturerCode::KAM, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::KAM, address.manufacturer_code());
         assert_eq!(12345678, address.serial_number.value::<u32>());
         assert_eq!(0x01, address.version);
         assert_eq!(DeviceType::Repeater, address.device_type().unwrap());
     }
 
+    #[test]
+    pub fn from_parts_unknown_manufacturer() {
+        let address =
+            WMBusAddress::from_parts(0x09B4, BcdNumber::new(12345678).unwrap(), 0x01, 0x99);
+
+        assert_eq!(ManufacturerCode::Other(0x09B4), address.manufacturer_code());
+        assert_eq!(12345678, address.serial_number());
+        assert_eq!(None, address.device_type());
+        assert_eq!(
+            [0xB4, 0x09, 0x78, 0x56, 0x34, 0x12, 0x01, 0x99],
+            address.get_bytes()
+        );
+    }
+
+    #[test]
+    pub fn can_get_manufacturer_string() {
+        let address = WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Water);
+        assert_eq!(Some(*b"KAM"), address.manufacturer_string());
+
+        let address = WMBusAddress::new(ManufacturerCode::DME, 12345678, 0x01, DeviceType::Water);
+        assert_eq!(Some(*b"DME"), address.manufacturer_string());
+
+        // The high bit is not part of the letters
+        let address = WMBusAddress::new(ManufacturerCode::APT, 12345678, 0x01, DeviceType::Water);
+        assert_eq!(Some(*b"APT"), address.manufacturer_string());
+
+        // A letter is outside A..=Z
+        let address =
+            WMBusAddress::from_parts(0x0000, BcdNumber::new(12345678).unwrap(), 0x01, 0x07);
+        assert_eq!(None, address.manufacturer_string());
+    }
+
+    #[test]
+    pub fn can_get_manufacturer_letters() {
+        for (manufacturer, letters) in [
+            (ManufacturerCode::APT, b"APT"),
+            (ManufacturerCode::DME, b"DME"),
+            (ManufacturerCode::GAV, b"GAV"),
+            (ManufacturerCode::HYD, b"HYD"),
+            (ManufacturerCode::KAM, b"KAM"),
+            (ManufacturerCode::KAW, b"KAW"),
+            (ManufacturerCode::LUG, b"LUG"),
+            (ManufacturerCode::SON, b"SON"),
+            (ManufacturerCode::TCH, b"TCH"),
+        ] {
+            assert_eq!(Some(*letters), manufacturer.letters());
+        }
+
+        // Manufacturers without a named variant
+        assert_eq!(Some(*b"ELS"), ManufacturerCode::Other(0x1593).letters());
+        assert_eq!(Some(*b"ZRI"), ManufacturerCode::Other(0x6A49).letters());
+        assert_eq!(None, ManufacturerCode::Other(0x0000).letters());
+    }
+
+    #[test]
+    pub fn compares_manufacturer_codes_by_code() {
+        use std::collections::HashSet;
+
+        assert_eq!(ManufacturerCode::KAM, ManufacturerCode::Other(0x2C2D));
+        assert_ne!(ManufacturerCode::KAM, ManufacturerCode::Other(0x2C37));
+
+        let codes: HashSet<_> = [ManufacturerCode::KAM, ManufacturerCode::Other(0x2C2D)].into();
+        assert_eq!(1, codes.len());
+    }
+
+    #[test]
+    pub fn can_get_code_from_letters() {
+        assert_eq!(Some(0x2C2D), ManufacturerCode::code_from_letters(*b"KAM"));
+        assert_eq!(Ok(ManufacturerCode::KAM), ManufacturerCode::try_from("KAM"));
+        assert_eq!(Some(0x8614), ManufacturerCode::code_from_letters(*b"APT"));
+        assert_eq!(Ok(ManufacturerCode::APT), ManufacturerCode::try_from("APT"));
+        assert_eq!(
+            Ok(ManufacturerCode::Other(0x1593)),
+            ManufacturerCode::try_from("ELS")
+        );
+
+        // Round-trip through the letters of an address
+        for letters in [*b"AAA", *b"APT", *b"DME", *b"ZZZ"] {
+            let code = ManufacturerCode::code_from_letters(letters).unwrap();
+            let address =
+                WMBusAddress::from_parts(code, BcdNumber::new(12345678).unwrap(), 0x01, 0x07);
+            assert_eq!(Some(letters), address.manufacturer_string());
+        }
+
+        assert_eq!(None, ManufacturerCode::code_from_letters(*b"kam"));
+        assert_eq!(None, ManufacturerCode::code_from_letters(*b"K[M"));
+        assert_eq!(Err(()), ManufacturerCode::try_from("KAMS"));
+    }
+
+    #[test]
+    pub fn can_create_with_manufacturer_str() {
+        let address =
+            WMBusAddress::with_manufacturer_str("KAM", 12345678, 0x01, DeviceType::Water).unwrap();
+        assert_eq!(0x2C2D, address.manufacturer_code);
+        assert_eq!(
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Water),
+            address
+        );
+        assert_eq!(Some(*b"KAM"), address.manufacturer_string());
+        assert_eq!(
+            WMBusAddress::new(ManufacturerCode::APT, 12345678, 0x01, DeviceType::Water),
+            WMBusAddress::with_manufacturer_str("APT", 12345678, 0x01, DeviceType::Water).unwrap()
+        );
+
+        for manufacturer in ["kam", "KA", "KAMS", "K@M", ""] {
+            assert_eq!(
+                Err(WMBusAddressError::ManufacturerString),
+                WMBusAddress::with_manufacturer_str(
+                    manufacturer,
+                    12345678,
+                    0x01,
+                    DeviceType::Water
+                )
+            );
+        }
+    }
+
+    #[test]
+    pub fn can_display_manufacturer_string() {
+        let address = WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Water);
+        assert!(std::format!("{}", address).starts_with("KAM:"));
+
+        // The high bit is not part of the letters
+        let address = WMBusAddress::new(ManufacturerCode::APT, 12345678, 0x01, DeviceType::Water);
+        assert!(std::format!("{}", address).starts_with("APT:"));
+
+        // A letter is outside A..=Z
+        let address =
+            WMBusAddress::from_parts(0x0000, BcdNumber::new(12345678).unwrap(), 0x01, 0x07);
+        assert!(std::format!("{}", address).starts_with("0x0:"));
+    }
+
+    #[test]
+    pub fn can_resolve() {
+        let address = WMBusAddress::new(ManufacturerCode::KAM, 1234567, 0x01, DeviceType::Water);
+        let resolved = address.resolve();
+        assert_eq!(Some("KAM"), resolved.manufacturer());
+        assert_eq!("01234567", resolved.serial_number());
+        assert_eq!(address, resolved.address);
+
+        let address = WMBusAddress::new(ManufacturerCode::APT, 1234567, 0x01, DeviceType::Water);
+        assert_eq!(Some("APT"), address.resolve().manufacturer());
+
+        let address =
+            WMBusAddress::from_parts(0x0000, BcdNumber::new(12345678).unwrap(), 0x01, 0x07);
+        let resolved = address.resolve();
+        assert_eq!(None, resolved.manufacturer());
+        assert_eq!("12345678", resolved.serial_number());
+    }
+
+    #[test]
+    pub fn parse_other_manufacturer() {
+        let address =
+            WMBusAddress::from_bytes([0xB4, 0x09, 0x78, 0x56, 0x34, 0x12, 0x01, 0x07]).unwrap();
+        assert_eq!(ManufacturerCode::Other(0x09B4), address.manufacturer_code());
+        assert_eq!(
+            [0xB4, 0x09, 0x78, 0x56, 0x34, 0x12, 0x01, 0x07],
+            WMBusAddress::new(
+                ManufacturerCode::Other(0x09B4),
+                12345678,
+                0x01,
+                DeviceType::Water
+            )
+            .get_bytes()
+        );
+        assert_eq!(ManufacturerCode::KAM, ManufacturerCode::from(0x2C2D));
+        assert_eq!(0x2C2D, u16::from(ManufacturerCode::KAM));
+    }
+
     #[test]
     pub fn parse_hydromenter_default() {
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x14, 0x89, 0x81, 0x44, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(44818914, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
@@ -189,59 +456,60 @@ pub mod tests {
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x91, 0x56, 0x39, 0x48, 0x20, 0x0C]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(48395691, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x95, 0x27, 0x80, 0x49, 0x20, 0x0C]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(49802795, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x59, 0x91, 0x95, 0x49, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(49959159, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x93, 0x56, 0x13, 0x51, 0x20, 0x0C]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(51135693, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x06, 0x34, 0x27, 0x51, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(51273406, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x02, 0x84, 0x84, 0x51, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(51848402, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x83, 0x70, 0x29, 0x53, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(53297083, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
     }
 
     #[test]
+    #[cfg(not(feature = "strict-layout"))]
     pub fn parse_hydromenter_reversed() {
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x85, 0x07, 0x47, 0x35, 0x04, 0x09]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(09043547, address.serial_number.value::<u32>());
         assert_eq!(0x85, address.version);
         assert_eq!(DeviceType::Water, address.device_type().unwrap());
@@ -256,84 +524,84 @@ pub mod tests {
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x85, 0x07, 0x25, 0x56, 0x00, 0x11]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(11005625, address.serial_number.value::<u32>());
         assert_eq!(0x85, address.version);
         assert_eq!(DeviceType::Water, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x31, 0x87, 0x81, 0x44]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(44818731, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x86, 0x88, 0x81, 0x44]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(44818886, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x70, 0x90, 0x81, 0x44]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(44819070, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x28, 0x87, 0x16, 0x46]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(46168728, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x04, 0x69, 0x02, 0x71, 0x47]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(47710269, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x18, 0x59, 0x78, 0x47]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(47785918, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x2B, 0x04, 0x41, 0x44, 0x87, 0x29]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(29874441, address.serial_number.value::<u32>());
         assert_eq!(0x2B, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x53, 0x0C, 0x95, 0x26, 0x86, 0x47]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(47862695, address.serial_number.value::<u32>());
         assert_eq!(0x53, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x0C, 0x61, 0x04, 0x34, 0x48]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(48340461, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::HeatInlet, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x20, 0x04, 0x02, 0x29, 0x27, 0x51]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(51272902, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
 
         let address =
             WMBusAddress::from_bytes([0x24, 0x23, 0x8B, 0x06, 0x29, 0x32, 0x26, 0x63]).unwrap();
-        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
         assert_eq!(63263229, address.serial_number.value::<u32>());
         assert_eq!(0x8B, address.version);
         assert_eq!(DeviceType::WarmWater, address.device_type().unwrap());
@@ -343,22 +611,38 @@ pub mod tests {
     pub fn parse_diehl_default() {
         let address =
             WMBusAddress::from_bytes([0xA5, 0x11, 0x55, 0x07, 0x16, 0x75, 0x20, 0x04]).unwrap();
-        assert_eq!(ManufacturerCode::DME, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::DME, address.manufacturer_code());
         assert_eq!(75160755, address.serial_number.value::<u32>());
         assert_eq!(0x20, address.version);
         assert_eq!(DeviceType::Heat, address.device_type().unwrap());
     }
 
     #[test]
+    #[cfg(not(feature = "strict-layout"))]
     pub fn parse_diehl_reversed() {
         let address =
             WMBusAddress::from_bytes([0xA5, 0x11, 0x78, 0x07, 0x79, 0x19, 0x48, 0x20]).unwrap();
-        assert_eq!(ManufacturerCode::DME, address.manufacturer_code().unwrap());
+        assert_eq!(ManufacturerCode::DME, address.manufacturer_code());
         assert_eq!(20481979, address.serial_number.value::<u32>());
         assert_eq!(0x78, address.version);
         assert_eq!(DeviceType::Water, address.device_type().unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "strict-layout")]
+    pub fn parse_hydrometer_strict() {
+        let address =
+            WMBusAddress::from_bytes([0x24, 0x23, 0x85, 0x07, 0x47, 0x35, 0x04, 0x09]).unwrap();
+        assert_eq!(ManufacturerCode::HYD, address.manufacturer_code());
+        assert_eq!(35470785, address.serial_number.value::<u32>());
+        assert_eq!(0x04, address.version);
+        assert_eq!(0x09, address.device_type);
+        assert_eq!(
+            [0x24, 0x23, 0x85, 0x07, 0x47, 0x35, 0x04, 0x09],
+            address.get_bytes()
+        );
+    }
+
     #[test]
     fn parse_error() {
         assert_eq!(
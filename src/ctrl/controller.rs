@@ -1,5 +1,8 @@
-use crate::stack::phl;
-use futures::Stream;
+use core::pin::pin;
+
+use crate::stack::{phl, Mode, Rssi};
+use embassy_time::{Duration, Instant, Timer};
+use futures::{Stream, StreamExt};
 use futures_async_stream::stream;
 
 use super::{
@@ -7,10 +10,48 @@ use super::{
     Frame,
 };
 
+/// The interval between channel RSSI samples while the channel is busy, see [`Controller::transmit_lbt()`]
+const LBT_BACKOFF: Duration = Duration::from_millis(5);
+
 /// Wireless M-Bus Transceiver Controller
 pub struct Controller<Transceiver: traits::Transceiver> {
     transceiver: Transceiver,
     listening: bool,
+    sniffer: bool,
+    stats: Stats,
+    partial: Option<Frame>,
+}
+
+/// Receive statistics
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// The number of fully received frames
+    pub frames: u32,
+    /// The number of frames discarded because of an invalid frame length
+    pub invalid_length: u32,
+    /// The number of frames discarded because they exceed the maximum packet length of the transceiver
+    pub oversized: u32,
+    /// The number of frames discarded because of invalid block CRC's
+    pub crc_errors: u32,
+}
+
+impl Stats {
+    /// Create new zeroed statistics
+    pub const fn new() -> Self {
+        Self {
+            frames: 0,
+            invalid_length: 0,
+            oversized: 0,
+            crc_errors: 0,
+        }
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
@@ -19,9 +60,32 @@ impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
         Self {
             transceiver,
             listening: false,
+            sniffer: false,
+            stats: Stats::new(),
+            partial: None,
         }
     }
 
+    /// Create a new controller in sniffer mode.
+    /// This is for transceivers that cannot be told the final frame length,
+    /// but instead continue to stream received bytes until e.g. the carrier drops.
+    /// `accept` is never invoked in sniffer mode, and frames are instead sliced from the received bytes
+    /// using the derived frame length. Any bytes following a frame are considered the start of the next frame.
+    pub const fn new_sniffer(transceiver: Transceiver) -> Self {
+        Self {
+            transceiver,
+            listening: false,
+            sniffer: true,
+            stats: Stats::new(),
+            partial: None,
+        }
+    }
+
+    /// Get the receive statistics
+    pub const fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
     /// Setup the transceiver and enter idle state.
     pub async fn init(&mut self) -> Result<(), Transceiver::Error> {
         self.listening = false;
@@ -42,6 +106,58 @@ impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
         self.transceiver.transmit().await
     }
 
+    /// Transmit pre-written bytes, retrying until a transmission succeeds.
+    /// The transmission is attempted at most `attempts` times with `interval` between each attempt,
+    /// and the error from the last attempt is returned if all attempts fail.
+    pub async fn transmit_with_retry(
+        &mut self,
+        attempts: usize,
+        interval: Duration,
+    ) -> Result<(), Transceiver::Error> {
+        assert!(!self.listening);
+        assert!(attempts > 0);
+
+        let mut attempt = 1;
+        loop {
+            match self.transceiver.transmit().await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt == attempts => return Err(e),
+                Err(_) => {
+                    attempt += 1;
+                    Timer::after(interval).await;
+                }
+            }
+        }
+    }
+
+    /// Transmit pre-written bytes with Listen-Before-Talk.
+    /// The receiver is started to sample the channel RSSI, and the bytes are only transmitted when the RSSI is below `threshold`.
+    /// The channel is sampled until it is clear or `timeout` expires, and `false` is returned if the channel remained busy.
+    pub async fn transmit_lbt(
+        &mut self,
+        threshold: Rssi,
+        timeout: Duration,
+    ) -> Result<bool, Transceiver::Error> {
+        assert!(!self.listening);
+
+        let deadline = Instant::now() + timeout;
+        self.transceiver.listen().await?;
+        loop {
+            if self.transceiver.get_rssi().await? < threshold {
+                break;
+            }
+            if Instant::now() >= deadline {
+                self.transceiver.idle().await?;
+                return Ok(false);
+            }
+            Timer::after(LBT_BACKOFF).await;
+        }
+
+        self.transceiver.idle().await?;
+        self.transceiver.transmit().await?;
+        Ok(true)
+    }
+
     /// Start and run receiver.
     /// Note that the receiver is _not_ stopped when the stream is dropped, so idle() must be called manually after the stream is dropped.
     pub async fn receive<'a>(
@@ -56,22 +172,45 @@ impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
         Ok(self.receive_stream())
     }
 
+    /// Start the receiver and forward the mode, bytes and rssi of each received frame to `forward` without decoding it.
+    /// This is the minimal work path for gateways that forward raw frames to be decoded upstream.
+    /// The receiver runs until `forward` returns `false`, after which it is stopped.
+    pub async fn receive_raw(
+        &mut self,
+        mut forward: impl FnMut(Mode, &[u8], Rssi) -> bool,
+    ) -> Result<(), Transceiver::Error> {
+        {
+            let mut stream = pin!(self.receive().await?);
+            while let Some(frame) = stream.next().await {
+                if !forward(frame.mode(), frame.bytes(), frame.rssi.unwrap()) {
+                    break;
+                }
+            }
+        }
+
+        self.idle().await
+    }
+
     #[stream(item = Frame)]
     async fn receive_stream(&mut self) {
+        let capabilities = self.transceiver.capabilities();
         loop {
             // Wait for frame to be detected
+            self.partial = None;
             let mut token = self
                 .transceiver
                 .receive(phl::DERIVE_FRAME_LENGTH_MIN)
                 .await
                 .unwrap();
-            let mut frame = Frame {
+            // The frame is kept in the controller so that it can be drained if the receiver is stopped mid-frame
+            let frame = self.partial.insert(Frame {
                 timestamp: token.timestamp(),
                 ..Default::default()
-            };
+            });
 
             // Frame was detected - read all frame bytes...
-            loop {
+            let mut frame_offset = 0;
+            'read: loop {
                 let received = self
                     .transceiver
                     .read(&mut token, &mut frame.buffer[frame.received..])
@@ -79,45 +218,96 @@ impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
 
                 if let Ok(received) = received {
                     // Things are progressing just fine - we are still receiving a frame
-
                     frame.received += received;
+                } else {
+                    // Error while reading - restart the receiver
+                    self.transceiver.idle().await.unwrap();
+                    self.transceiver.listen().await.unwrap();
+                    break;
+                }
 
+                // The received bytes may contain multiple frames when in sniffer mode
+                loop {
                     if frame.len.is_none() {
                         // Try and derive the frame length
                         match phl::FrameMetadata::read(&frame.buffer[..frame.received]) {
                             Ok(metadata) => {
                                 let receive_length = metadata.frame_offset + metadata.frame_length;
-                                self.transceiver
-                                    .accept(&mut token, receive_length)
-                                    .await
-                                    .unwrap();
+                                if !self.sniffer
+                                    && receive_length > self.transceiver.max_packet_length()
+                                {
+                                    // The radio cannot receive the frame - wait for a new frame to be received
+                                    self.stats.oversized += 1;
+                                    break 'read;
+                                }
+                                if !self.sniffer {
+                                    self.transceiver
+                                        .accept(&mut token, receive_length)
+                                        .await
+                                        .unwrap();
+                                }
+                                frame_offset = metadata.frame_offset;
                                 frame.mode = Some(metadata.mode);
                                 frame.len = Some(receive_length);
                                 frame.rssi = Some(self.transceiver.get_rssi().await.unwrap());
+                                frame.frequency = self.transceiver.get_frequency().await.unwrap();
+                                frame.frequency_offset = token.frequency_offset();
+                                if capabilities.lqi {
+                                    frame.preamble_quality = token.preamble_quality();
+                                }
                             }
                             Err(phl::Error::Incomplete) => {
                                 // We need more bytes to derive the frame length
-                                continue;
+                                continue 'read;
                             }
                             Err(_) => {
                                 // Invalid frame length - wait for a new frame to be received
-                                break;
+                                self.stats.invalid_length += 1;
+                                break 'read;
                             }
                         }
                     }
 
-                    if let Some(frame_length) = frame.len {
-                        if frame.received >= frame_length {
-                            // Frame is fully received
-                            yield frame;
-                            break;
+                    let frame_length = frame.len.unwrap();
+                    if frame.received < frame_length {
+                        // We need more bytes to complete the frame
+                        continue 'read;
+                    }
+
+                    // Frame is fully received
+                    self.stats.frames += 1;
+                    #[cfg(feature = "defmt")]
+                    defmt::debug!(
+                        "wmbus frame {} {=[u8]:02x}",
+                        frame.mode(),
+                        &frame.buffer[..frame_length]
+                    );
+                    let valid = capabilities.hardware_crc
+                        || phl::verify_crc(&frame.buffer[frame_offset..frame_length], frame.mode())
+                            .is_ok();
+                    if !valid {
+                        self.stats.crc_errors += 1;
+                    }
+                    if !self.sniffer {
+                        if valid {
+                            yield self.partial.take().unwrap();
                         }
+                        break 'read;
+                    }
+
+                    // The receiver continues to stream bytes in sniffer mode,
+                    // so any bytes following the frame are the start of the next frame
+                    let mut next = Frame::default();
+                    let remaining = frame.received - frame_length;
+                    next.buffer[..remaining]
+                        .copy_from_slice(&frame.buffer[frame_length..frame.received]);
+                    next.received = remaining;
+                    frame.received = frame_length;
+
+                    let received = core::mem::replace(frame, next);
+                    if valid {
+                        yield received;
                     }
-                } else {
-                    // Error while reading - restart the receiver
-                    self.transceiver.idle().await.unwrap();
-                    self.transceiver.listen().await.unwrap();
-                    break;
                 }
             }
         }
@@ -127,11 +317,500 @@ impl<Transceiver: traits::Transceiver> Controller<Transceiver> {
     pub async fn idle(&mut self) -> Result<(), Transceiver::Error> {
         self.transceiver.idle().await?;
         self.listening = false;
+        self.partial = None;
         Ok(())
     }
 
+    /// Stop the receiver, and return any partially received frame.
+    /// The received bytes of the frame are available through [`Frame::received_bytes()`],
+    /// and the frame length and mode are only available if they were derived before the receiver was stopped.
+    pub async fn idle_draining(&mut self) -> Result<Option<Frame>, Transceiver::Error> {
+        let partial = self.partial.take();
+        self.idle().await?;
+        Ok(partial.filter(|frame| frame.received > 0))
+    }
+
     /// Release the transceiver
     pub fn release(self) -> Transceiver {
         self.transceiver
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::pin::pin;
+
+    use embassy_time::{Duration, Instant};
+    use futures::{executor::block_on, StreamExt};
+    use mockall::Sequence;
+
+    use crate::{
+        ctrl::traits::{stubs::RxTokenStub, Capabilities, MockTransceiver},
+        stack::{phl, Mode, Rssi, Stack},
+    };
+
+    use super::*;
+
+    #[test]
+    fn can_transmit_with_retry() {
+        // Given
+        let mut transceiver = MockTransceiver::new();
+        let mut seq = Sequence::new();
+        transceiver
+            .expect_transmit()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Err(()));
+        transceiver
+            .expect_transmit()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let result = block_on(controller.transmit_with_retry(3, Duration::from_millis(1)));
+
+        // Then
+        assert_eq!(Ok(()), result);
+    }
+
+    #[test]
+    fn transmit_with_retry_gives_up() {
+        // Given
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_transmit().times(2).returning(|| Err(()));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let result = block_on(controller.transmit_with_retry(2, Duration::from_millis(1)));
+
+        // Then
+        assert_eq!(Err(()), result);
+    }
+
+    #[test]
+    fn transmit_lbt_waits_for_clear_channel() {
+        // Given
+        let mut transceiver = MockTransceiver::new();
+        let mut seq = Sequence::new();
+        transceiver
+            .expect_listen()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        transceiver
+            .expect_get_rssi()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(-60));
+        transceiver
+            .expect_get_rssi()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(-100));
+        transceiver
+            .expect_idle()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        transceiver
+            .expect_transmit()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let transmitted = block_on(controller.transmit_lbt(-85, Duration::from_secs(1)));
+
+        // Then
+        assert_eq!(Ok(true), transmitted);
+    }
+
+    #[test]
+    fn transmit_lbt_gives_up_on_busy_channel() {
+        // Given
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-60));
+        transceiver.expect_idle().times(1).returning(|| Ok(()));
+        transceiver.expect_transmit().never();
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let transmitted = block_on(controller.transmit_lbt(-85, Duration::from_millis(20)));
+
+        // Then
+        assert_eq!(Ok(false), transmitted);
+    }
+
+    #[test]
+    fn can_receive_in_sniffer_mode() {
+        // Given
+        #[rustfmt::skip]
+        let first = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+        let mut second = first;
+        second[12] = 0xA1;
+        second[20..].copy_from_slice(&[0x4E, 0x34]);
+        let mut stream = [0; 44];
+        stream[..22].copy_from_slice(&first);
+        stream[22..].copy_from_slice(&second);
+
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver
+            .expect_receive()
+            .times(1)
+            .returning(|_| Ok(RxTokenStub::new(Instant::now())));
+        // The receiver streams bytes in chunks that do not align with the frame boundaries
+        let mut position = 0;
+        transceiver.expect_read().returning(move |_, buffer| {
+            let chunk = &stream[position..(position + 7).min(stream.len())];
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            position += chunk.len();
+            Ok(chunk.len())
+        });
+        // The receiver does not support accept
+        transceiver.expect_accept().returning(|_, _| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-70));
+        transceiver.expect_get_frequency().returning(|| Ok(None));
+        transceiver
+            .expect_capabilities()
+            .return_const(Capabilities::new());
+        let mut controller = Controller::new_sniffer(transceiver);
+
+        // When
+        let frames = block_on(async {
+            let stream = controller.receive().await.unwrap();
+            stream.take(2).collect::<std::vec::Vec<_>>().await
+        });
+
+        // Then
+        assert_eq!(2, frames.len());
+        assert_eq!(Mode::ModeCFFB, frames[0].mode());
+        assert_eq!(&first, frames[0].bytes());
+        assert_eq!(Mode::ModeCFFB, frames[1].mode());
+        assert_eq!(&second, frames[1].bytes());
+    }
+
+    #[test]
+    fn discards_frame_exceeding_max_packet_length() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+        // The first frame announces a length of 2 + 0x21 bytes
+        let oversized = [0x54, 0x3D, 0x20];
+
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver
+            .expect_receive()
+            .times(2)
+            .returning(|_| Ok(RxTokenStub::new(Instant::now())));
+        let mut seq = Sequence::new();
+        transceiver
+            .expect_read()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, buffer| {
+                buffer[..oversized.len()].copy_from_slice(&oversized);
+                Ok(oversized.len())
+            });
+        transceiver
+            .expect_read()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, buffer| {
+                buffer[..frame.len()].copy_from_slice(&frame);
+                Ok(frame.len())
+            });
+        transceiver
+            .expect_capabilities()
+            .return_const(Capabilities::new());
+        transceiver.expect_max_packet_length().return_const(32usize);
+        transceiver
+            .expect_accept()
+            .withf(|_, frame_length| *frame_length == 22)
+            .times(1)
+            .returning(|_, _| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-70));
+        transceiver.expect_get_frequency().returning(|| Ok(None));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let received = block_on(async {
+            let mut stream = pin!(controller.receive().await.unwrap());
+            stream.next().await.unwrap()
+        });
+
+        // Then
+        assert_eq!(&frame, received.bytes());
+        assert_eq!(
+            &Stats {
+                frames: 1,
+                invalid_length: 0,
+                oversized: 1,
+                crc_errors: 0,
+            },
+            controller.stats()
+        );
+    }
+
+    fn receive_corrupted_then_valid(capabilities: Capabilities) -> (Frame, Stats) {
+        #[rustfmt::skip]
+        let valid = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+        let mut corrupted = valid;
+        corrupted[12] = 0xA1;
+
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver
+            .expect_receive()
+            .returning(|_| Ok(RxTokenStub::new(Instant::now())));
+        let mut frames = [corrupted, valid].into_iter();
+        transceiver.expect_read().returning(move |_, buffer| {
+            let frame = frames.next().unwrap();
+            buffer[..frame.len()].copy_from_slice(&frame);
+            Ok(frame.len())
+        });
+        transceiver.expect_capabilities().return_const(capabilities);
+        transceiver
+            .expect_max_packet_length()
+            .return_const(phl::FRAME_MAX);
+        transceiver.expect_accept().returning(|_, _| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-70));
+        transceiver.expect_get_frequency().returning(|| Ok(None));
+        let mut controller = Controller::new(transceiver);
+
+        let received = block_on(async {
+            let mut stream = pin!(controller.receive().await.unwrap());
+            stream.next().await.unwrap()
+        });
+        (received, *controller.stats())
+    }
+
+    #[test]
+    fn discards_frame_with_invalid_crc() {
+        // When
+        let (received, stats) = receive_corrupted_then_valid(Capabilities::new());
+
+        // Then
+        assert_eq!(0xA0, received.bytes()[12]);
+        assert_eq!(2, stats.frames);
+        assert_eq!(1, stats.crc_errors);
+    }
+
+    #[test]
+    fn skips_crc_verification_with_hardware_crc() {
+        // When
+        let (received, stats) = receive_corrupted_then_valid(Capabilities {
+            hardware_crc: true,
+            ..Capabilities::new()
+        });
+
+        // Then
+        assert_eq!(0xA1, received.bytes()[12]);
+        assert_eq!(1, stats.frames);
+        assert_eq!(0, stats.crc_errors);
+    }
+
+    #[test]
+    fn can_receive_rx_token_metrics() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver.expect_receive().times(1).returning(|_| {
+            Ok(RxTokenStub {
+                frequency_offset: Some(-1200),
+                preamble_quality: Some(42),
+                ..RxTokenStub::new(Instant::now())
+            })
+        });
+        transceiver
+            .expect_read()
+            .times(1)
+            .returning(move |_, buffer| {
+                buffer[..frame.len()].copy_from_slice(&frame);
+                Ok(frame.len())
+            });
+        transceiver
+            .expect_capabilities()
+            .return_const(Capabilities {
+                lqi: true,
+                ..Capabilities::new()
+            });
+        transceiver
+            .expect_max_packet_length()
+            .return_const(phl::FRAME_MAX);
+        transceiver.expect_accept().returning(|_, _| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-70));
+        transceiver
+            .expect_get_frequency()
+            .returning(|| Ok(Some(868_950_000)));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let received = block_on(async {
+            let mut stream = pin!(controller.receive().await.unwrap());
+            stream.next().await.unwrap()
+        });
+        let packet = Stack::new().read_from_frame(&received).unwrap();
+
+        // Then
+        assert_eq!(Some(-1200), received.frequency_offset);
+        assert_eq!(Some(42), received.preamble_quality);
+        assert_eq!(Some(-1200), packet.frequency_offset);
+        assert_eq!(Some(42), packet.preamble_quality);
+        assert_eq!(Some(868_950_000), received.frequency);
+        assert_eq!(Some(868_950_000), packet.frequency);
+    }
+
+    #[test]
+    fn can_receive_raw() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+
+        let mut transceiver = MockTransceiver::new();
+        transceiver.expect_listen().times(1).returning(|| Ok(()));
+        transceiver
+            .expect_receive()
+            .times(1)
+            .returning(|_| Ok(RxTokenStub::new(Instant::now())));
+        transceiver
+            .expect_read()
+            .times(1)
+            .returning(move |_, buffer| {
+                buffer[..frame.len()].copy_from_slice(&frame);
+                Ok(frame.len())
+            });
+        transceiver
+            .expect_capabilities()
+            .return_const(Capabilities::new());
+        transceiver
+            .expect_max_packet_length()
+            .return_const(phl::FRAME_MAX);
+        transceiver.expect_accept().returning(|_, _| Ok(()));
+        transceiver.expect_get_rssi().returning(|| Ok(-70));
+        transceiver.expect_get_frequency().returning(|| Ok(None));
+        transceiver.expect_idle().times(1).returning(|| Ok(()));
+        let mut controller = Controller::new(transceiver);
+
+        // When
+        let mut forwarded = std::vec::Vec::new();
+        block_on(controller.receive_raw(|mode, bytes, rssi| {
+            forwarded.push((mode, bytes.to_vec(), rssi));
+            false
+        }))
+        .unwrap();
+
+        // Then
+        assert_eq!(vec![(Mode::ModeCFFB, frame.to_vec(), -70)], forwarded);
+    }
+
+    /// Transceiver that receives the first part of a frame after which the remaining bytes never arrive
+    struct StalledTransceiver(Option<&'static [u8]>);
+
+    impl traits::Transceiver for StalledTransceiver {
+        type RxToken = RxTokenStub;
+        type Error = ();
+
+        async fn init(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn write(&mut self, _buffer: &[u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn transmit(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn listen(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn get_rssi(&mut self) -> Result<Rssi, Self::Error> {
+            Ok(-70)
+        }
+
+        async fn receive(&mut self, _min_frame_length: usize) -> Result<RxTokenStub, Self::Error> {
+            Ok(RxTokenStub::new(Instant::now()))
+        }
+
+        async fn read(
+            &mut self,
+            _token: &mut RxTokenStub,
+            buffer: &mut [u8],
+        ) -> Result<usize, Self::Error> {
+            match self.0.take() {
+                Some(received) => {
+                    buffer[..received.len()].copy_from_slice(received);
+                    Ok(received.len())
+                }
+                None => futures::future::pending().await,
+            }
+        }
+
+        async fn accept(
+            &mut self,
+            _token: &mut RxTokenStub,
+            _frame_length: usize,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn idle(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn can_drain_partial_frame_on_idle() {
+        // Given
+        let received = &[0x54, 0x3D, 0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12];
+        let mut controller = Controller::new(StalledTransceiver(Some(received)));
+
+        // When
+        let partial = block_on(async {
+            {
+                let mut stream = pin!(controller.receive().await.unwrap());
+                assert!(futures::poll!(stream.next()).is_pending());
+            }
+            controller.idle_draining().await.unwrap()
+        });
+
+        // Then
+        let partial = partial.unwrap();
+        assert_eq!(received, partial.received_bytes());
+        assert_eq!(Some(22), partial.try_len());
+        assert_eq!(Some(Mode::ModeCFFB), partial.try_mode());
+        assert_eq!(None, partial.try_bytes());
+        assert!(block_on(controller.idle_draining()).unwrap().is_none());
+    }
+}
@@ -1,7 +1,10 @@
 mod controller;
+#[cfg(feature = "spi")]
+pub mod spi;
 pub mod traits;
+pub mod wrappers;
 
-pub use controller::Controller;
+pub use controller::{Controller, Stats};
 use embassy_time::Instant;
 
 use crate::stack::{phl, Layer, Mode, Packet, ReadError, Rssi, Stack};
@@ -9,6 +12,10 @@ use crate::stack::{phl, Layer, Mode, Packet, ReadError, Rssi, Stack};
 pub struct Frame {
     pub timestamp: Instant,
     pub rssi: Option<Rssi>,
+    pub frequency_offset: Option<i32>,
+    pub preamble_quality: Option<u8>,
+    /// The frequency in Hz that the frame was received on, see [`traits::Transceiver::get_frequency()`]
+    pub frequency: Option<u32>,
     buffer: [u8; phl::FRAME_MAX],
     received: usize,
     mode: Option<Mode>,
@@ -20,6 +27,9 @@ impl Default for Frame {
         Self {
             timestamp: Instant::now(),
             rssi: None,
+            frequency_offset: None,
+            preamble_quality: None,
+            frequency: None,
             buffer: [0; phl::FRAME_MAX],
             received: 0,
             mode: None,
@@ -30,23 +40,100 @@ impl Default for Frame {
 
 #[allow(clippy::len_without_is_empty)]
 impl Frame {
+    /// Get the frame length.
+    /// Panics if the frame length is not yet derived, see [`Frame::try_len()`].
     pub fn len(&self) -> usize {
         self.len.unwrap()
     }
 
+    /// Get the frame bytes.
+    /// Panics if the frame length is not yet derived, see [`Frame::try_bytes()`].
     pub fn bytes(&self) -> &[u8] {
         &self.buffer[0..self.len.unwrap()]
     }
 
+    /// Get the frame mode.
+    /// Panics if the frame mode is not yet derived, see [`Frame::try_mode()`].
     pub fn mode(&self) -> Mode {
         self.mode.unwrap()
     }
+
+    /// Get the bytes received so far, which may be less than the frame length.
+    pub fn received_bytes(&self) -> &[u8] {
+        &self.buffer[0..self.received]
+    }
+
+    /// Get the frame length, or `None` if it is not yet derived.
+    pub fn try_len(&self) -> Option<usize> {
+        self.len
+    }
+
+    /// Get the frame bytes, or `None` if the frame is not yet fully received.
+    pub fn try_bytes(&self) -> Option<&[u8]> {
+        self.len
+            .filter(|&len| self.received >= len)
+            .map(|len| &self.buffer[0..len])
+    }
+
+    /// Get the frame mode, or `None` if it is not yet derived.
+    pub fn try_mode(&self) -> Option<Mode> {
+        self.mode
+    }
+
+    /// Get the number of invalid 3oo6 symbols in a fully received Mode T frame, see [`crate::modet::symbol_error_count()`].
+    /// This is `None` for other modes, or if the frame is not yet fully received.
+    /// Unlike [`Packet::symbol_error_count`], this is also available for a frame that fails to read.
+    pub fn symbol_error_count(&self) -> Option<usize> {
+        self.try_mode()
+            .filter(|&mode| mode == Mode::ModeTMTO)
+            .and(self.try_bytes())
+            .map(crate::modet::symbol_error_count)
+    }
 }
 
 impl<A: Layer> Stack<A> {
     pub fn read_from_frame(&self, frame: &Frame) -> Result<Packet, ReadError> {
-        let mut packet = self.read(frame.bytes(), frame.mode())?;
+        let bytes = frame.try_bytes().ok_or(ReadError::Incomplete)?;
+        let mode = frame.try_mode().ok_or(ReadError::Incomplete)?;
+        let mut packet = self.read(bytes, mode)?;
         packet.rssi = frame.rssi;
+        packet.frequency_offset = frame.frequency_offset;
+        packet.preamble_quality = frame.preamble_quality;
+        packet.frequency = frame.frequency;
         Ok(packet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_frame_is_incomplete() {
+        let frame = Frame::default();
+
+        assert_eq!(None, frame.try_len());
+        assert_eq!(None, frame.try_bytes());
+        assert_eq!(None, frame.try_mode());
+        assert_eq!(
+            Err(ReadError::Incomplete),
+            Stack::new().read_from_frame(&frame).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_count_symbol_errors_of_modet_frame() {
+        let mut frame = Frame::default();
+        // 0x0A encoded as 010110 100110, an invalid symbol, and 0x0 encoded as 010110
+        frame.buffer[..3].copy_from_slice(&[0x5A, 0x6F, 0xD6]);
+        frame.received = 3;
+        frame.len = Some(3);
+        assert_eq!(None, frame.symbol_error_count());
+
+        frame.mode = Some(Mode::ModeTMTO);
+        assert_eq!(Some(1), frame.symbol_error_count());
+
+        frame.mode = Some(Mode::ModeCFFA);
+        assert_eq!(None, frame.symbol_error_count());
+    }
+}
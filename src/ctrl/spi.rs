@@ -0,0 +1,318 @@
+use embassy_time::Instant;
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::stack::{phl, Rssi};
+
+use super::traits::{RxToken, Transceiver};
+
+/// The driver surface required by [`SpiTransceiver`].
+///
+/// The driver implements the chip specific register access and state machine handling,
+/// e.g. for a CC1101 or SX126x, while the adapter implements [`Transceiver`] on top of it.
+/// The driver is assumed to already be configured for the relevant Wireless M-Bus mode by `init`.
+pub trait Radio<S: SpiDevice> {
+    /// Reset and configure the radio, and enter idle state.
+    async fn init(&mut self, spi: &mut S) -> Result<(), S::Error>;
+
+    /// Enter idle state.
+    async fn idle(&mut self, spi: &mut S) -> Result<(), S::Error>;
+
+    /// Write bytes to the transmit fifo.
+    async fn write_fifo(&mut self, spi: &mut S, buffer: &[u8]) -> Result<(), S::Error>;
+
+    /// Transmit the bytes in the transmit fifo and wait for the transmission to complete.
+    /// The radio must return to idle state after the transmission.
+    async fn transmit(&mut self, spi: &mut S) -> Result<(), S::Error>;
+
+    /// Start the receiver.
+    async fn listen(&mut self, spi: &mut S) -> Result<(), S::Error>;
+
+    /// Wait until a frame is detected and at least `min_length` bytes are in the receive fifo.
+    async fn wait_for_frame(&mut self, spi: &mut S, min_length: usize) -> Result<(), S::Error>;
+
+    /// Read the bytes currently in the receive fifo and return the number of bytes read.
+    async fn read_fifo(&mut self, spi: &mut S, buffer: &mut [u8]) -> Result<usize, S::Error>;
+
+    /// Set the final frame length for the current receive.
+    /// The radio must re-start the receiver when `frame_length` bytes have been received.
+    async fn set_frame_length(&mut self, spi: &mut S, frame_length: usize) -> Result<(), S::Error>;
+
+    /// Get the current rssi.
+    async fn get_rssi(&mut self, spi: &mut S) -> Result<Rssi, S::Error>;
+
+    /// Get the maximum packet length in bytes that the radio can receive.
+    fn max_packet_length(&self) -> usize {
+        phl::FRAME_MAX
+    }
+}
+
+/// [`Transceiver`] adapter for a [`Radio`] driver on an `embedded-hal-async` SPI device
+pub struct SpiTransceiver<S: SpiDevice, R: Radio<S>> {
+    spi: S,
+    radio: R,
+}
+
+/// Receive token for [`SpiTransceiver`]
+pub struct SpiRxToken {
+    timestamp: Instant,
+}
+
+impl<S: SpiDevice, R: Radio<S>> SpiTransceiver<S, R> {
+    /// Create a new transceiver adapter
+    pub const fn new(spi: S, radio: R) -> Self {
+        Self { spi, radio }
+    }
+
+    /// Release the SPI device and the radio driver
+    pub fn release(self) -> (S, R) {
+        (self.spi, self.radio)
+    }
+}
+
+impl<S: SpiDevice, R: Radio<S>> Transceiver for SpiTransceiver<S, R> {
+    type RxToken = SpiRxToken;
+    type Error = S::Error;
+
+    async fn init(&mut self) -> Result<(), Self::Error> {
+        self.radio.init(&mut self.spi).await
+    }
+
+    async fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
+        self.radio.write_fifo(&mut self.spi, buffer).await
+    }
+
+    async fn transmit(&mut self) -> Result<(), Self::Error> {
+        self.radio.transmit(&mut self.spi).await
+    }
+
+    async fn listen(&mut self) -> Result<(), Self::Error> {
+        self.radio.listen(&mut self.spi).await
+    }
+
+    fn max_packet_length(&self) -> usize {
+        self.radio.max_packet_length()
+    }
+
+    async fn get_rssi(&mut self) -> Result<Rssi, Self::Error> {
+        self.radio.get_rssi(&mut self.spi).await
+    }
+
+    async fn receive(&mut self, min_frame_length: usize) -> Result<Self::RxToken, Self::Error> {
+        self.radio
+            .wait_for_frame(&mut self.spi, min_frame_length)
+            .await?;
+        Ok(SpiRxToken {
+            timestamp: Instant::now(),
+        })
+    }
+
+    async fn read(
+        &mut self,
+        _token: &mut Self::RxToken,
+        buffer: &mut [u8],
+    ) -> Result<usize, Self::Error> {
+        self.radio.read_fifo(&mut self.spi, buffer).await
+    }
+
+    async fn accept(
+        &mut self,
+        _token: &mut Self::RxToken,
+        frame_length: usize,
+    ) -> Result<(), Self::Error> {
+        self.radio
+            .set_frame_length(&mut self.spi, frame_length)
+            .await
+    }
+
+    async fn idle(&mut self) -> Result<(), Self::Error> {
+        self.radio.idle(&mut self.spi).await
+    }
+}
+
+impl RxToken for SpiRxToken {
+    fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{convert::Infallible, pin::pin};
+    use std::{collections::VecDeque, vec::Vec};
+
+    use embedded_hal_async::spi::{ErrorType, Operation};
+    use futures::{executor::block_on, StreamExt};
+
+    use crate::{ctrl::Controller, stack::Mode};
+
+    use super::*;
+
+    const CMD_STROBE_IDLE: u8 = 0x01;
+    const CMD_STROBE_RX: u8 = 0x02;
+    const CMD_STROBE_TX: u8 = 0x03;
+    const CMD_RX_BYTES: u8 = 0x10;
+    const CMD_RSSI: u8 = 0x11;
+    const CMD_FRAME_LENGTH: u8 = 0x12;
+    const CMD_FIFO: u8 = 0x20;
+    const FIFO_SIZE: usize = 8;
+
+    /// A fake radio chip with a small fifo and a command byte followed by data
+    #[derive(Default)]
+    struct MockSpi {
+        rx: VecDeque<u8>,
+        tx: Vec<u8>,
+        commands: Vec<u8>,
+        frame_length: Option<u8>,
+    }
+
+    impl ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockSpi {
+        async fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            let [Operation::Write(command), rest @ ..] = operations else {
+                panic!("A transaction must start with the command");
+            };
+            let (command, data) = command.split_first().unwrap();
+            self.commands.push(*command);
+            match (*command, rest) {
+                (CMD_RX_BYTES, [Operation::Read(buf)]) => {
+                    buf[0] = self.rx.len().min(FIFO_SIZE) as u8
+                }
+                (CMD_RSSI, [Operation::Read(buf)]) => buf[0] = -70i8 as u8,
+                (CMD_FIFO, [Operation::Read(buf)]) => {
+                    for byte in buf.iter_mut() {
+                        *byte = self.rx.pop_front().unwrap();
+                    }
+                }
+                (CMD_FIFO, [Operation::Write(buf)]) => self.tx.extend_from_slice(buf),
+                (CMD_FRAME_LENGTH, []) => self.frame_length = Some(data[0]),
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    struct MockRadio;
+
+    impl<S: SpiDevice> Radio<S> for MockRadio {
+        async fn init(&mut self, spi: &mut S) -> Result<(), S::Error> {
+            spi.write(&[CMD_STROBE_IDLE]).await
+        }
+
+        async fn idle(&mut self, spi: &mut S) -> Result<(), S::Error> {
+            spi.write(&[CMD_STROBE_IDLE]).await
+        }
+
+        async fn write_fifo(&mut self, spi: &mut S, buffer: &[u8]) -> Result<(), S::Error> {
+            spi.transaction(&mut [Operation::Write(&[CMD_FIFO]), Operation::Write(buffer)])
+                .await
+        }
+
+        async fn transmit(&mut self, spi: &mut S) -> Result<(), S::Error> {
+            spi.write(&[CMD_STROBE_TX]).await
+        }
+
+        async fn listen(&mut self, spi: &mut S) -> Result<(), S::Error> {
+            spi.write(&[CMD_STROBE_RX]).await
+        }
+
+        async fn wait_for_frame(&mut self, spi: &mut S, min_length: usize) -> Result<(), S::Error> {
+            let mut rx_bytes = [0];
+            while (rx_bytes[0] as usize) < min_length {
+                spi.transaction(&mut [
+                    Operation::Write(&[CMD_RX_BYTES]),
+                    Operation::Read(&mut rx_bytes),
+                ])
+                .await?;
+            }
+            Ok(())
+        }
+
+        async fn read_fifo(&mut self, spi: &mut S, buffer: &mut [u8]) -> Result<usize, S::Error> {
+            let mut rx_bytes = [0];
+            spi.transaction(&mut [
+                Operation::Write(&[CMD_RX_BYTES]),
+                Operation::Read(&mut rx_bytes),
+            ])
+            .await?;
+            let len = buffer.len().min(rx_bytes[0] as usize);
+            spi.transaction(&mut [
+                Operation::Write(&[CMD_FIFO]),
+                Operation::Read(&mut buffer[..len]),
+            ])
+            .await?;
+            Ok(len)
+        }
+
+        async fn set_frame_length(
+            &mut self,
+            spi: &mut S,
+            frame_length: usize,
+        ) -> Result<(), S::Error> {
+            spi.write(&[CMD_FRAME_LENGTH, frame_length as u8]).await
+        }
+
+        async fn get_rssi(&mut self, spi: &mut S) -> Result<Rssi, S::Error> {
+            let mut rssi = [0];
+            spi.transaction(&mut [Operation::Write(&[CMD_RSSI]), Operation::Read(&mut rssi)])
+                .await?;
+            Ok(rssi[0] as i8 as Rssi)
+        }
+    }
+
+    #[test]
+    fn can_receive_frame() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+        let spi = MockSpi {
+            rx: VecDeque::from(frame.to_vec()),
+            ..Default::default()
+        };
+        let mut controller = Controller::new(SpiTransceiver::new(spi, MockRadio));
+
+        // When
+        let received = block_on(async {
+            controller.init().await.unwrap();
+            let mut stream = pin!(controller.receive().await.unwrap());
+            stream.next().await.unwrap()
+        });
+        block_on(controller.idle()).unwrap();
+
+        // Then
+        assert_eq!(Mode::ModeCFFB, received.mode());
+        assert_eq!(&frame, received.bytes());
+        assert_eq!(Some(-70), received.rssi);
+
+        let (spi, _) = controller.release().release();
+        assert_eq!(Some(frame.len() as u8), spi.frame_length);
+        assert_eq!([CMD_STROBE_IDLE, CMD_STROBE_RX], spi.commands[..2]);
+        assert_eq!(Some(&CMD_STROBE_IDLE), spi.commands.last());
+    }
+
+    #[test]
+    fn can_transmit_frame() {
+        // Given
+        let mut controller = Controller::new(SpiTransceiver::new(MockSpi::default(), MockRadio));
+
+        // When
+        block_on(async {
+            controller.write(&[0x01, 0x02, 0x03]).await.unwrap();
+            controller.transmit().await.unwrap();
+        });
+
+        // Then
+        let (spi, _) = controller.release().release();
+        assert_eq!([0x01, 0x02, 0x03], spi.tx.as_slice());
+        assert_eq!([CMD_FIFO, CMD_STROBE_TX], spi.commands.as_slice());
+    }
+}
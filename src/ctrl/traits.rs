@@ -4,7 +4,7 @@ use embassy_time::Instant;
 #[cfg(test)]
 use mockall::automock;
 
-use crate::stack::Rssi;
+use crate::stack::{phl, Rssi};
 
 #[cfg_attr(test, automock(type RxToken = stubs::RxTokenStub; type Error = ();))]
 pub trait Transceiver {
@@ -23,9 +23,25 @@ pub trait Transceiver {
     /// Start the receiver.
     async fn listen(&mut self) -> Result<(), Self::Error>;
 
+    /// Get the maximum packet length in bytes that the transceiver can receive.
+    fn max_packet_length(&self) -> usize {
+        phl::FRAME_MAX
+    }
+
+    /// Get the capabilities of the transceiver.
+    /// The maximum packet length is reported separately, see [`Transceiver::max_packet_length()`].
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new()
+    }
+
     /// Get the current rssi.
     async fn get_rssi(&mut self) -> Result<Rssi, Self::Error>;
 
+    /// Get the current frequency in Hz, if supported by the transceiver, e.g. when it hops between channels.
+    async fn get_frequency(&mut self) -> Result<Option<u32>, Self::Error> {
+        Ok(None)
+    }
+
     /// Try and receive a frame.
     /// The future will complete when `min_frame_length` frame bytes are received.
     /// The receiver will continue to receive the frame until either `accept` is invoked or `receive` are re-invoked.
@@ -50,9 +66,47 @@ pub trait Transceiver {
     async fn idle(&mut self) -> Result<(), Self::Error>;
 }
 
+/// The capabilities of a transceiver
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+    /// The transceiver verifies the block CRC's and never delivers frames with invalid CRC's,
+    /// so the controller can skip the software CRC verification
+    pub hardware_crc: bool,
+    /// The transceiver reports the link quality through [`RxToken::preamble_quality()`]
+    pub lqi: bool,
+}
+
+impl Capabilities {
+    /// Create the capabilities of a transceiver without any hardware support
+    pub const fn new() -> Self {
+        Self {
+            hardware_crc: false,
+            lqi: false,
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub trait RxToken {
     /// Get the start-of-frame timestamp
     fn timestamp(&self) -> Instant;
+
+    /// Get the frequency offset in Hz of the received frame, if supported by the transceiver
+    fn frequency_offset(&self) -> Option<i32> {
+        None
+    }
+
+    /// Get the preamble quality of the received frame, if supported by the transceiver.
+    /// The scale is transceiver specific, where a higher value is better.
+    fn preamble_quality(&self) -> Option<u8> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -61,11 +115,33 @@ pub mod stubs {
 
     use super::RxToken;
 
-    pub struct RxTokenStub(pub Instant);
+    pub struct RxTokenStub {
+        pub timestamp: Instant,
+        pub frequency_offset: Option<i32>,
+        pub preamble_quality: Option<u8>,
+    }
+
+    impl RxTokenStub {
+        pub const fn new(timestamp: Instant) -> Self {
+            Self {
+                timestamp,
+                frequency_offset: None,
+                preamble_quality: None,
+            }
+        }
+    }
 
     impl RxToken for RxTokenStub {
         fn timestamp(&self) -> Instant {
-            self.0
+            self.timestamp
+        }
+
+        fn frequency_offset(&self) -> Option<i32> {
+            self.frequency_offset
+        }
+
+        fn preamble_quality(&self) -> Option<u8> {
+            self.preamble_quality
         }
     }
 }
@@ -0,0 +1,201 @@
+//! The IMST (e.g. iM871A) host controller interface.
+//!
+//! A received frame is sent by the module as the message `A5 <control|endpoint> <message id> <length> <payload>`,
+//! optionally followed by a four byte timestamp, a one byte RSSI and a two byte CRC.
+//! The payload is the frame data without the L field and without the block CRC's, see [`ImstFrame::write_frame()`].
+
+use bytes::BufMut;
+use crc::{Crc, CRC_16_IBM_SDLC};
+
+use crate::stack::{phl, WriteError};
+
+/// The start of frame byte
+const START_OF_FRAME: u8 = 0xA5;
+/// The control field flag for an attached timestamp
+const TIMESTAMP_ATTACHED: u8 = 0x20;
+/// The control field flag for an attached RSSI
+const RSSI_ATTACHED: u8 = 0x40;
+/// The control field flag for an attached CRC
+const CRC_ATTACHED: u8 = 0x80;
+/// The radio link endpoint
+const RADIOLINK_ID: u8 = 0x02;
+/// The message id for a received frame on the radio link endpoint
+const RADIOLINK_MSG_WMBUSMSG_IND: u8 = 0x03;
+/// The minimum payload length, i.e. the C field, the A field and the CI field
+const MIN_PAYLOAD_LENGTH: usize = 1 + 8 + 1;
+
+const CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_SDLC);
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    Incomplete,
+    /// The message does not start with the start of frame byte
+    StartOfFrame,
+    /// The message is not a received frame, e.g. a response to a command
+    UnexpectedMessage,
+    /// The payload is too short to include the C field, the A field and the CI field
+    InvalidLength,
+    Crc,
+}
+
+/// A frame received by an IMST module
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ImstFrame<'a> {
+    /// The frame data starting with the C field and without block CRC's
+    pub data: &'a [u8],
+    /// The module timestamp, if attached
+    pub timestamp: Option<u32>,
+    /// The raw module specific RSSI value, if attached
+    pub rssi: Option<u8>,
+}
+
+/// Read a received frame message and return the frame.
+/// The CRC is validated if it is attached.
+pub fn read(message: &[u8]) -> Result<ImstFrame<'_>, Error> {
+    if message.len() < 4 {
+        return Err(Error::Incomplete);
+    }
+    if message[0] != START_OF_FRAME {
+        return Err(Error::StartOfFrame);
+    }
+
+    let control = message[1];
+    if control & 0x0F != RADIOLINK_ID || message[2] != RADIOLINK_MSG_WMBUSMSG_IND {
+        return Err(Error::UnexpectedMessage);
+    }
+
+    let len = message[3] as usize;
+    if len < MIN_PAYLOAD_LENGTH {
+        return Err(Error::InvalidLength);
+    }
+    let timestamp_len = if control & TIMESTAMP_ATTACHED != 0 {
+        4
+    } else {
+        0
+    };
+    let rssi_len = if control & RSSI_ATTACHED != 0 { 1 } else { 0 };
+    let crc_len = if control & CRC_ATTACHED != 0 { 2 } else { 0 };
+    let message_len = 4 + len + timestamp_len + rssi_len + crc_len;
+    if message.len() < message_len {
+        return Err(Error::Incomplete);
+    }
+
+    if crc_len > 0 {
+        let crc_offset = message_len - 2;
+        let crc = u16::from_le_bytes([message[crc_offset], message[crc_offset + 1]]);
+        if CRC.checksum(&message[1..crc_offset]) != crc {
+            return Err(Error::Crc);
+        }
+    }
+
+    let mut offset = 4 + len;
+    let timestamp = (timestamp_len > 0)
+        .then(|| u32::from_le_bytes(message[offset..offset + 4].try_into().unwrap()));
+    offset += timestamp_len;
+    let rssi = (rssi_len > 0).then(|| message[offset]);
+
+    Ok(ImstFrame {
+        data: &message[4..4 + len],
+        timestamp,
+        rssi,
+    })
+}
+
+impl ImstFrame<'_> {
+    /// Write the frame as a Mode C frame format A frame which can be read by [`crate::stack::Stack::read()`]
+    pub fn write_frame(&self, writer: &mut impl BufMut) -> Result<(), WriteError> {
+        phl::write_ffa_frame(writer, self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stack::{Mode, Stack};
+
+    use super::*;
+
+    #[test]
+    fn can_read_imst_frame() {
+        // Given
+        #[rustfmt::skip]
+        let message = [
+            0xA5, 0x42, 0x03, 0x11,
+            0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x5A, // RSSI
+        ];
+
+        // When
+        let imst = read(&message).unwrap();
+        let mut frame = [0; phl::FRAME_MAX];
+        let mut writer = &mut frame[..];
+        imst.write_frame(&mut writer).unwrap();
+        let written = phl::FRAME_MAX - writer.len();
+        let packet = Stack::new()
+            .read(&frame[..written], Mode::ModeCFFA)
+            .unwrap();
+
+        // Then
+        assert_eq!(None, imst.timestamp);
+        assert_eq!(Some(0x5A), imst.rssi);
+        let dll = packet.dll.unwrap();
+        assert_eq!(0x44, dll.control);
+        assert_eq!(12345678, dll.address.serial_number());
+        assert_eq!(
+            &[0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+            packet.apl.as_slice()
+        );
+    }
+
+    #[test]
+    fn can_validate_crc() {
+        #[rustfmt::skip]
+        let message = [
+            0xA5, 0xC2, 0x03, 0x11,
+            0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x5A, // RSSI
+            0xD0, 0xE3, // CRC
+        ];
+        assert_eq!(Some(0x5A), read(&message).unwrap().rssi);
+
+        let mut corrupted = message;
+        corrupted[10] = 0xFF;
+        assert_eq!(Err(Error::Crc), read(&corrupted));
+        assert_eq!(Err(Error::Incomplete), read(&message[..22]));
+    }
+
+    #[test]
+    fn can_reject_other_messages() {
+        // Ping response on the device management endpoint
+        assert_eq!(
+            Err(Error::UnexpectedMessage),
+            read(&[0xA5, 0x01, 0x02, 0x00])
+        );
+        assert_eq!(Err(Error::StartOfFrame), read(&[0x00, 0x42, 0x03, 0x00]));
+    }
+
+    #[test]
+    fn can_reject_short_payload() {
+        assert_eq!(Err(Error::InvalidLength), read(&[0xA5, 0x02, 0x03, 0x00]));
+        assert_eq!(
+            Err(Error::InvalidLength),
+            read(&[0xA5, 0x02, 0x03, 0x03, 0x44, 0x2D, 0x2C])
+        );
+
+        // A short frame is also rejected when written directly
+        let frame = ImstFrame {
+            data: &[0x44, 0x2D, 0x2C],
+            timestamp: None,
+            rssi: None,
+        };
+        let mut buf = [0; phl::FRAME_MAX];
+        let mut writer = &mut buf[..];
+        assert_eq!(
+            Err(WriteError::DataTooShort),
+            frame.write_frame(&mut writer)
+        );
+    }
+}
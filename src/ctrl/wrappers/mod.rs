@@ -0,0 +1,2 @@
+//! Unwrapping of frames received through radio modules that use their own host protocol
+pub mod imst;
@@ -0,0 +1,118 @@
+use heapless::Deque;
+
+/// Transmit duty cycle tracker
+///
+/// The tracker accumulates the airtime of transmissions within a rolling window,
+/// e.g. the 0.1% per hour that applies to parts of the 868 MHz band.
+/// Timestamps are microseconds from any monotonic clock, and at most `N` transmissions are tracked individually.
+pub struct DutyCycleTracker<const N: usize> {
+    window_us: u64,
+    budget_us: u64,
+    transmissions: Deque<Transmission, N>,
+}
+
+struct Transmission {
+    timestamp_us: u64,
+    airtime_us: u32,
+}
+
+impl<const N: usize> DutyCycleTracker<N> {
+    /// Create a new tracker with a duty cycle given in parts per million of the window
+    pub const fn new(window_us: u64, duty_cycle_ppm: u32) -> Self {
+        Self {
+            window_us,
+            budget_us: (window_us * duty_cycle_ppm as u64) / 1_000_000,
+            transmissions: Deque::new(),
+        }
+    }
+
+    /// Record a transmission that started at `timestamp_us`
+    pub fn record(&mut self, timestamp_us: u64, airtime_us: u32) {
+        self.expire(timestamp_us);
+
+        let mut airtime_us = airtime_us;
+        if self.transmissions.is_full() {
+            // Merge the oldest transmission into the next so that its airtime expires later rather than never being accounted for
+            let oldest = self.transmissions.pop_front().unwrap();
+            match self.transmissions.front_mut() {
+                Some(next) => next.airtime_us += oldest.airtime_us,
+                None => airtime_us += oldest.airtime_us,
+            }
+        }
+
+        self.transmissions
+            .push_back(Transmission {
+                timestamp_us,
+                airtime_us,
+            })
+            .ok()
+            .unwrap();
+    }
+
+    /// Get the airtime used within the window ending at `now_us`
+    pub fn used_us(&mut self, now_us: u64) -> u64 {
+        self.expire(now_us);
+        self.transmissions
+            .iter()
+            .map(|transmission| transmission.airtime_us as u64)
+            .sum()
+    }
+
+    /// Get the remaining airtime budget within the window ending at `now_us`
+    pub fn remaining_us(&mut self, now_us: u64) -> u64 {
+        self.budget_us.saturating_sub(self.used_us(now_us))
+    }
+
+    /// Get whether a transmission with the given airtime can start at `now_us` without exceeding the budget
+    pub fn can_transmit(&mut self, now_us: u64, airtime_us: u32) -> bool {
+        airtime_us as u64 <= self.remaining_us(now_us)
+    }
+
+    fn expire(&mut self, now_us: u64) {
+        while let Some(oldest) = self.transmissions.front() {
+            if oldest.timestamp_us + self.window_us > now_us {
+                break;
+            }
+            self.transmissions.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_US: u64 = 3600 * 1_000_000;
+
+    #[test]
+    fn can_track_budget() {
+        // 0.1% of an hour is 3.6s
+        let mut tracker = DutyCycleTracker::<8>::new(HOUR_US, 1000);
+        assert_eq!(3_600_000, tracker.remaining_us(0));
+
+        tracker.record(0, 1_000_000);
+        tracker.record(1_000_000, 2_000_000);
+        assert_eq!(3_000_000, tracker.used_us(2_000_000));
+        assert_eq!(600_000, tracker.remaining_us(2_000_000));
+        assert!(tracker.can_transmit(2_000_000, 600_000));
+        assert!(!tracker.can_transmit(2_000_000, 600_001));
+
+        // The first transmission leaves the window
+        assert_eq!(1_600_000, tracker.remaining_us(HOUR_US));
+
+        // Both transmissions leave the window
+        assert_eq!(3_600_000, tracker.remaining_us(HOUR_US + 1_000_000));
+    }
+
+    #[test]
+    fn can_track_more_than_capacity() {
+        let mut tracker = DutyCycleTracker::<2>::new(HOUR_US, 1000);
+        tracker.record(0, 1000);
+        tracker.record(1, 1000);
+        tracker.record(2, 1000);
+        assert_eq!(3000, tracker.used_us(2));
+
+        // The merged airtime expires with the second transmission
+        assert_eq!(1000, tracker.used_us(HOUR_US + 1));
+    }
+}
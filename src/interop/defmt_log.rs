@@ -0,0 +1,62 @@
+//! Extraction of frames from `defmt` logs.
+//!
+//! The controller logs each received frame at debug level when the `defmt` feature is enabled, e.g.
+//! `wmbus frame ModeCFFB [54, 3d, 13, 44, ...]`, i.e. the marker, the mode and the hex encoded frame bytes.
+
+use std::vec::Vec;
+
+use crate::stack::Mode;
+
+/// The marker preceding a frame in the log
+const MARKER: &str = "wmbus frame ";
+
+/// Extract the frames and their modes from a decoded `defmt` log, e.g. captured over RTT.
+/// Lines that do not contain a valid frame are ignored.
+pub fn extract_frames(log: &str) -> Vec<(Vec<u8>, Mode)> {
+    log.lines().filter_map(extract_frame).collect()
+}
+
+fn extract_frame(line: &str) -> Option<(Vec<u8>, Mode)> {
+    let (_, line) = line.split_once(MARKER)?;
+    let (mode, bytes) = line.split_once(' ')?;
+    let mode = *Mode::all()
+        .iter()
+        .find(|candidate| std::format!("{:?}", candidate) == mode)?;
+    let bytes = bytes.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let bytes = bytes
+        .split(',')
+        .map(|byte| u8::from_str_radix(byte.trim(), 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((bytes, mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stack::Stack;
+
+    use super::*;
+
+    #[test]
+    fn can_extract_frames() {
+        // Given
+        let log = "\
+0.000000 INFO  Starting receiver
+└─ app::____embassy_main_task::{async_fn#0} @ src/main.rs:42
+0.512345 DEBUG wmbus frame ModeCFFB [54, 3d, 13, 44, 2d, 2c, 78, 56, 34, 12, 01, 32, a0, 00, 01, 02, 03, 04, 05, 06, c3, c0]
+└─ wmbus::ctrl::controller::{impl#1}::receive_stream::{async_block#0} @ src/ctrl/controller.rs:187
+0.612345 DEBUG wmbus frame ModeCFFB [54, 3d, zz]
+";
+
+        // When
+        let frames = extract_frames(log);
+
+        // Then
+        assert_eq!(1, frames.len());
+        let (bytes, mode) = &frames[0];
+        assert_eq!(Mode::ModeCFFB, *mode);
+        assert_eq!(22, bytes.len());
+
+        let packet = Stack::new().read(bytes, *mode).unwrap();
+        assert_eq!(12345678, packet.dll.unwrap().address.serial_number());
+    }
+}
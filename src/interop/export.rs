@@ -0,0 +1,187 @@
+//! A flat and versioned representation of a decoded packet for export to e.g. a message bus.
+//!
+//! The schema is decoupled from the internal [`Packet`] layout, so that it can be mapped to e.g. protobuf or flatbuffers.
+//! Any breaking change to the schema increments [`EXPORT_SCHEMA_VERSION`].
+
+use std::{string::String, vec::Vec};
+
+use crate::stack::{
+    apl::records::{Quantity, Unit},
+    Mode, Packet,
+};
+
+/// The version of the [`ExportRecord`] schema
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// A decoded packet for export
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRecord {
+    /// The schema version, see [`EXPORT_SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// The three letter manufacturer code, e.g. "KAM", or empty if the packet has no data link layer or the code has no letters
+    pub manufacturer: String,
+    pub serial_number: u32,
+    pub version: u8,
+    pub device_type: u8,
+    /// The mode, e.g. "ModeCFFB"
+    pub mode: String,
+    pub rssi: Option<i16>,
+    /// The values of the unencrypted data records that have a known unit
+    pub values: Vec<ExportValue>,
+}
+
+/// A scaled data record value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportValue {
+    /// The quantity, e.g. "Energy"
+    pub name: String,
+    pub value: f64,
+    /// The unit, e.g. "WattHour"
+    pub unit: String,
+}
+
+impl<const N: usize> From<&Packet<N>> for ExportRecord {
+    fn from(packet: &Packet<N>) -> Self {
+        let address = packet.dll.as_ref().map(|dll| &dll.address);
+        let values = packet
+            .data_records()
+            .into_iter()
+            .flatten()
+            .map_while(Result::ok)
+            .filter_map(|record| {
+                let info = record.value_information()?;
+                let (value, unit) = record.scaled_value()?;
+                Some(ExportValue {
+                    name: quantity_name(info.quantity).into(),
+                    value,
+                    unit: unit_name(unit).into(),
+                })
+            })
+            .collect();
+
+        Self {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            manufacturer: address
+                .and_then(|address| address.manufacturer_code().letters())
+                .map(|letters| letters.into_iter().map(char::from).collect())
+                .unwrap_or_default(),
+            serial_number: address.map(|address| address.serial_number()).unwrap_or(0),
+            version: address.map(|address| address.version).unwrap_or(0),
+            device_type: address.map(|address| address.device_type).unwrap_or(0),
+            mode: mode_name(packet.mode).into(),
+            rssi: packet.rssi,
+            values,
+        }
+    }
+}
+
+// The names are part of the schema, so they must not change if the variants are renamed
+
+const fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::ModeCFFA => "ModeCFFA",
+        Mode::ModeCFFB => "ModeCFFB",
+        Mode::ModeTMTO => "ModeTMTO",
+        Mode::ModeTMTODecoded => "ModeTMTODecoded",
+        Mode::ModeS => "ModeS",
+        Mode::ModeTOTM => "ModeTOTM",
+    }
+}
+
+const fn quantity_name(quantity: Quantity) -> &'static str {
+    match quantity {
+        Quantity::Energy => "Energy",
+        Quantity::Volume => "Volume",
+        Quantity::Mass => "Mass",
+        Quantity::OnTime => "OnTime",
+        Quantity::OperatingTime => "OperatingTime",
+        Quantity::Power => "Power",
+        Quantity::VolumeFlow => "VolumeFlow",
+        Quantity::MassFlow => "MassFlow",
+        Quantity::FlowTemperature => "FlowTemperature",
+        Quantity::ReturnTemperature => "ReturnTemperature",
+        Quantity::TemperatureDifference => "TemperatureDifference",
+        Quantity::ExternalTemperature => "ExternalTemperature",
+        Quantity::Pressure => "Pressure",
+        Quantity::TimePoint => "TimePoint",
+        Quantity::HcaUnits => "HcaUnits",
+        Quantity::AveragingDuration => "AveragingDuration",
+        Quantity::ActualityDuration => "ActualityDuration",
+        Quantity::FabricationNumber => "FabricationNumber",
+        Quantity::EnhancedIdentification => "EnhancedIdentification",
+        Quantity::BusAddress => "BusAddress",
+    }
+}
+
+const fn unit_name(unit: Unit) -> &'static str {
+    match unit {
+        Unit::WattHour => "WattHour",
+        Unit::Joule => "Joule",
+        Unit::CubicMeter => "CubicMeter",
+        Unit::Kilogram => "Kilogram",
+        Unit::Second => "Second",
+        Unit::Minute => "Minute",
+        Unit::Hour => "Hour",
+        Unit::Day => "Day",
+        Unit::Watt => "Watt",
+        Unit::JoulePerHour => "JoulePerHour",
+        Unit::CubicMeterPerHour => "CubicMeterPerHour",
+        Unit::CubicMeterPerMinute => "CubicMeterPerMinute",
+        Unit::CubicMeterPerSecond => "CubicMeterPerSecond",
+        Unit::KilogramPerHour => "KilogramPerHour",
+        Unit::Celsius => "Celsius",
+        Unit::Kelvin => "Kelvin",
+        Unit::Bar => "Bar",
+        Unit::None => "None",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stack::{Mode, Stack};
+
+    use super::*;
+
+    #[test]
+    fn can_export_packet() {
+        #[rustfmt::skip]
+        let frame = [
+            0x1C, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+            0x7A, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume
+            0x04, 0x06, 0x01, 0x00, 0x00, 0x00, // Energy
+            0x84, 0xE8,
+        ];
+        let mut packet = Stack::new().read(&frame, Mode::ModeCFFB).unwrap();
+        packet.rssi = Some(-70);
+
+        let record = ExportRecord::from(&packet);
+
+        assert_eq!(1, record.schema_version);
+        assert_eq!("KAM", record.manufacturer);
+        assert_eq!(12345678, record.serial_number);
+        assert_eq!(0x01, record.version);
+        assert_eq!(0x04, record.device_type);
+        assert_eq!("ModeCFFB", record.mode);
+        assert_eq!(Some(-70), record.rssi);
+        assert_eq!(
+            vec![
+                ExportValue {
+                    name: "Volume".into(),
+                    value: 0.042,
+                    unit: "CubicMeter".into(),
+                },
+                ExportValue {
+                    name: "Energy".into(),
+                    value: 1000.0,
+                    unit: "WattHour".into(),
+                },
+            ],
+            record.values
+        );
+
+        // A manufacturer code with letters outside A..=Z
+        packet.dll.as_mut().unwrap().address.manufacturer_code = 0x0000;
+        assert_eq!("", ExportRecord::from(&packet).manufacturer);
+    }
+}
@@ -0,0 +1,4 @@
+//! Host side interoperability helpers
+
+pub mod defmt_log;
+pub mod export;
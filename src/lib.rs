@@ -1,4 +1,4 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(async_fn_in_trait)]
 #![allow(incomplete_features)]
 #![feature(const_trait_impl)]
@@ -7,31 +7,42 @@
 
 #[macro_use]
 extern crate num_derive;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::hash::{Hash, Hasher};
 
 mod address;
 #[cfg(feature = "ctrl")]
 pub mod ctrl;
+pub mod dutycycle;
+#[cfg(feature = "std")]
+pub mod interop;
 pub mod modec;
+pub mod modes;
 pub mod modet;
 pub mod stack;
 
 #[cfg(feature = "defmt")]
 mod defmt_impl;
 
-pub use address::WMBusAddress;
+pub use address::{ResolvedAddress, WMBusAddress};
 
-#[derive(Clone, Copy, Debug, PartialEq, FromPrimitive)]
-#[repr(u16)]
+/// A manufacturer code, where manufacturers are equal if their numeric codes are equal,
+/// e.g. `ManufacturerCode::Other(0x2C2D)` is equal to `ManufacturerCode::KAM`
+#[derive(Clone, Copy, Debug)]
 pub enum ManufacturerCode {
-    APT = 0x8614, // Apator
-    DME = 0x11A5, // Diehl
-    GAV = 0x1C36, // Carlo Gavazzi
-    HYD = 0x2324, // Hydrometer
-    KAM = 0x2C2D, // Kamstrup
-    KAW = 0x2C37, // Kamstrup Water
-    LUG = 0x32A7, // Landis+Gyr GmbH
-    SON = 0x4DEE, // Sontex
-    TCH = 0x5068, // Techem
+    APT, // Apator
+    DME, // Diehl
+    GAV, // Carlo Gavazzi
+    HYD, // Hydrometer
+    KAM, // Kamstrup
+    KAW, // Kamstrup Water
+    LUG, // Landis+Gyr GmbH
+    SON, // Sontex
+    TCH, // Techem
+    /// A manufacturer without a named variant
+    Other(u16),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, FromPrimitive)]
@@ -51,11 +62,126 @@ pub enum DeviceType {
     Repeater = 0x32,
 }
 
-impl TryFrom<u16> for ManufacturerCode {
+impl ManufacturerCode {
+    /// The manufacturers with a named variant
+    const NAMED: [ManufacturerCode; 9] = [
+        ManufacturerCode::APT,
+        ManufacturerCode::DME,
+        ManufacturerCode::GAV,
+        ManufacturerCode::HYD,
+        ManufacturerCode::KAM,
+        ManufacturerCode::KAW,
+        ManufacturerCode::LUG,
+        ManufacturerCode::SON,
+        ManufacturerCode::TCH,
+    ];
+
+    /// Get the numeric manufacturer code
+    pub const fn code(&self) -> u16 {
+        match self {
+            ManufacturerCode::APT => 0x8614,
+            ManufacturerCode::DME => 0x11A5,
+            ManufacturerCode::GAV => 0x1C36,
+            ManufacturerCode::HYD => 0x2324,
+            ManufacturerCode::KAM => 0x2C2D,
+            ManufacturerCode::KAW => 0x2C37,
+            ManufacturerCode::LUG => 0x32A7,
+            ManufacturerCode::SON => 0x4DEE,
+            ManufacturerCode::TCH => 0x5068,
+            ManufacturerCode::Other(code) => *code,
+        }
+    }
+
+    /// Get the three letters of the manufacturer code, e.g. `b"KAM"`.
+    /// The letters are packed with 5 bits each as the letter value minus 64, and the most significant bit is ignored.
+    /// `None` is returned if a letter is outside `A..=Z`.
+    pub const fn letters(&self) -> Option<[u8; 3]> {
+        let code = self.code();
+        let letters = [
+            64 + ((code >> 10) & 0x1F) as u8,
+            64 + ((code >> 5) & 0x1F) as u8,
+            64 + (code & 0x1F) as u8,
+        ];
+        let mut i = 0;
+        while i < letters.len() {
+            if !letters[i].is_ascii_uppercase() {
+                return None;
+            }
+            i += 1;
+        }
+
+        Some(letters)
+    }
+
+    /// Get the numeric manufacturer code of three letters, e.g. `*b"KAM"`, see [`ManufacturerCode::letters()`].
+    /// As the most significant bit is not part of the letters, it is only set for a named manufacturer that uses it, e.g. [`ManufacturerCode::APT`].
+    /// `None` is returned if a letter is outside `A..=Z`.
+    pub const fn code_from_letters(letters: [u8; 3]) -> Option<u16> {
+        let mut code = 0;
+        let mut i = 0;
+        while i < letters.len() {
+            if !letters[i].is_ascii_uppercase() {
+                return None;
+            }
+            code = (code << 5) | (letters[i] - 64) as u16;
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < Self::NAMED.len() {
+            if Self::NAMED[i].code() & 0x7FFF == code {
+                return Some(Self::NAMED[i].code());
+            }
+            i += 1;
+        }
+
+        Some(code)
+    }
+}
+
+impl PartialEq for ManufacturerCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for ManufacturerCode {}
+
+impl Hash for ManufacturerCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+    }
+}
+
+impl From<u16> for ManufacturerCode {
+    fn from(value: u16) -> Self {
+        match value {
+            0x8614 => ManufacturerCode::APT,
+            0x11A5 => ManufacturerCode::DME,
+            0x1C36 => ManufacturerCode::GAV,
+            0x2324 => ManufacturerCode::HYD,
+            0x2C2D => ManufacturerCode::KAM,
+            0x2C37 => ManufacturerCode::KAW,
+            0x32A7 => ManufacturerCode::LUG,
+            0x4DEE => ManufacturerCode::SON,
+            0x5068 => ManufacturerCode::TCH,
+            _ => ManufacturerCode::Other(value),
+        }
+    }
+}
+
+impl TryFrom<&str> for ManufacturerCode {
     type Error = ();
 
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
-        num_traits::FromPrimitive::from_u16(value).ok_or(())
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let letters = value.as_bytes().try_into().map_err(|_| ())?;
+        Self::code_from_letters(letters).map(Self::from).ok_or(())
+    }
+}
+
+impl From<ManufacturerCode> for u16 {
+    fn from(value: ManufacturerCode) -> Self {
+        value.code()
     }
 }
 
@@ -1,3 +1,63 @@
 pub const FFA_SYNCWORD: [u8; 4] = [0x54, 0x3D, 0x54, 0xCD];
 pub const FFB_SYNCWORD: [u8; 4] = [0x54, 0x3D, 0x54, 0x3D];
 pub const CHIPRATE: u32 = 100_000; // kcps
+pub const PREAMBLE_CHIPS: usize = 16 * 2; // n x (01) where n >= 16
+pub const SYNCWORD_CHIPS: usize = 8 * FFA_SYNCWORD.len();
+pub const POSTAMBLE_CHIPS: usize = 2;
+
+/// Known bit patterns for radio bring-up, e.g. to verify the RF path before transmitting frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TestPattern {
+    /// Alternating zeros and ones, i.e. 0x55, like the preamble
+    Alternating,
+    /// All ones, i.e. an unmodulated carrier at the upper frequency deviation
+    AllOnes,
+    /// The PN9 sequence with polynomial x^9 + x^5 + 1 and seed 0x1FF
+    Pn9,
+}
+
+impl TestPattern {
+    /// Get the bytes of the pattern
+    pub fn bytes(self) -> impl Iterator<Item = u8> {
+        let mut pn9: u16 = 0x1FF;
+        core::iter::repeat_with(move || match self {
+            TestPattern::Alternating => 0x55,
+            TestPattern::AllOnes => 0xFF,
+            TestPattern::Pn9 => {
+                let byte = pn9 as u8;
+                for _ in 0..8 {
+                    pn9 = (pn9 >> 1) | (((pn9 ^ (pn9 >> 5)) & 1) << 8);
+                }
+                byte
+            }
+        })
+    }
+}
+
+/// Write `len` bytes of a test pattern into `out`.
+/// Mode C is not encoded, so the bytes are transmitted as is.
+pub fn test_pattern(kind: TestPattern, len: usize, out: &mut [u8]) {
+    for (byte, value) in out[..len].iter_mut().zip(kind.bytes()) {
+        *byte = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_generate_alternating_pattern() {
+        let mut out = [0; 5];
+        test_pattern(TestPattern::Alternating, 4, &mut out);
+        assert_eq!([0x55, 0x55, 0x55, 0x55, 0x00], out);
+    }
+
+    #[test]
+    fn can_generate_pn9_pattern() {
+        let mut out = [0; 8];
+        test_pattern(TestPattern::Pn9, 8, &mut out);
+        assert_eq!([0xFF, 0xE1, 0x1D, 0x9A, 0xED, 0x85, 0x33, 0x24], out);
+    }
+}
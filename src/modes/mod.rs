@@ -0,0 +1,7 @@
+pub const SYNCWORD: [u8; 2] = [0x76, 0x96];
+pub const CHIPRATE: u32 = 32_768; // cps
+pub const PREAMBLE_CHIPS: usize = 15 * 2; // n x (01) where n >= 15 for the short preamble
+pub const SYNCWORD_CHIPS: usize = 18; // 000111011010010110
+pub const POSTAMBLE_CHIPS: usize = 2;
+/// Each bit is Manchester encoded into two chips
+pub const CHIPS_PER_BYTE: usize = 8 * 2;
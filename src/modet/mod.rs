@@ -1,19 +1,82 @@
-use crate::stack::phl::FrameFormat;
+use bitvec::prelude::*;
+
+use crate::{modec::TestPattern, stack::phl::FrameFormat};
 
 pub mod threeoutofsix;
 
 pub const SYNCWORD: [u8; 2] = [0x54, 0x3D];
 pub const CHIPRATE: u32 = 100_000; // kcps
+pub const PREAMBLE_CHIPS: usize = 19 * 2; // n x (01) where n >= 19
+pub const SYNCWORD_CHIPS: usize = 10; // 0000111101
+pub const POSTAMBLE_CHIPS: usize = 2;
 pub const THREE_OUT_OF_SIX_ENCODED_MAX: usize = (crate::stack::phl::FFA::FRAME_MAX * 6) / 4;
 
+/// Write `len` bytes of a test pattern 3oo6 encoded into `out`, and get the number of bits written.
+/// See [`crate::modec::test_pattern()`] for the bytes before encoding.
+pub fn test_pattern(
+    kind: TestPattern,
+    len: usize,
+    out: &mut [u8],
+) -> Result<usize, threeoutofsix::Error> {
+    let out = out.view_bits_mut::<Msb0>();
+    let mut written = 0;
+    for byte in kind.bytes().take(len) {
+        written += threeoutofsix::ThreeOutOfSix::encode(&mut out[written..], &[byte])?;
+    }
+    Ok(written)
+}
+
+/// Count the 6 bit symbols in a 3oo6 encoded `buffer` that are not valid 3oo6 symbols.
+/// The count is a measure of the link quality, e.g. when aiming an antenna, also for frames that fail to decode.
+/// Any trailing bits that do not make up a complete symbol are ignored.
+/// Symbols that are valid but differ from those transmitted are not counted, see [`crate::stack::phl::symbol_error_count()`] for a known frame.
+pub fn symbol_error_count(buffer: &[u8]) -> usize {
+    buffer
+        .view_bits::<Msb0>()
+        .chunks_exact(6)
+        .filter(|symbol| !threeoutofsix::ThreeOutOfSix::is_valid_symbol(symbol.load_be()))
+        .count()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn can_count_symbol_errors() {
+        let mut encoded = [0; 6];
+        let bits = threeoutofsix::ThreeOutOfSix::encode(
+            encoded.view_bits_mut(),
+            &[0x12, 0x34, 0x56, 0x78],
+        )
+        .unwrap();
+        assert_eq!(48, bits);
+        assert_eq!(0, symbol_error_count(&encoded));
+
+        // Corrupt the first and the last symbol
+        encoded[0] ^= 0x80;
+        encoded[5] ^= 0x01;
+        assert_eq!(2, symbol_error_count(&encoded));
+        assert_eq!(1, symbol_error_count(&encoded[..5]));
+    }
+
     #[test]
     fn encoded_max() {
         const FRAME_MAX: usize = 2 + 256 + 16 * 2;
         assert_eq!(290, FRAME_MAX);
         assert_eq!(435, THREE_OUT_OF_SIX_ENCODED_MAX);
     }
+
+    #[test]
+    fn can_generate_alternating_pattern() {
+        let mut out = [0; 3];
+        assert_eq!(Ok(24), test_pattern(TestPattern::Alternating, 2, &mut out));
+        // 0x5 is encoded as 011001
+        assert_eq!([0x65, 0x96, 0x59], out);
+
+        assert_eq!(
+            Err(threeoutofsix::Error::Capacity),
+            test_pattern(TestPattern::Alternating, 3, &mut out)
+        );
+    }
 }
@@ -64,6 +64,10 @@ impl ThreeOutOfSix {
             return Err(Error::InputLength);
         }
 
+        if buffer.len() < symbols.len() / 2 {
+            return Err(Error::Capacity);
+        }
+
         let mut written = 0;
         let mut carry = None;
 
@@ -82,8 +86,18 @@ impl ThreeOutOfSix {
             }
         }
 
+        // A dangling high nibble would otherwise be silently dropped
+        if carry.is_some() {
+            return Err(Error::InputLength);
+        }
+
         Ok(written)
     }
+
+    /// Get whether a 6 bit symbol is in the decode table
+    pub const fn is_valid_symbol(symbol: u8) -> bool {
+        (symbol as usize) < DECODE_TABLE.len() && DECODE_TABLE[symbol as usize] != -1
+    }
 }
 
 #[cfg(test)]
@@ -142,4 +156,39 @@ pub mod tests {
         let decoded = ThreeOutOfSix::decode(&mut decode_buf, &encode_buf[..encoded]).unwrap();
         assert_eq!(data, decode_buf[..decoded]);
     }
+
+    #[test]
+    pub fn decode_rejects_dangling_nibble() {
+        let mut encode_buf = bitarr![u8, Msb0; 0; 24];
+        let encoded = ThreeOutOfSix::encode(&mut encode_buf, &[0x12, 0x34]).unwrap();
+        assert_eq!(24, encoded);
+
+        // Three symbols would leave the last nibble without a pair
+        let mut decode_buf = [0; 2];
+        assert_eq!(
+            Err(Error::InputLength),
+            ThreeOutOfSix::decode(&mut decode_buf, &encode_buf[..18])
+        );
+        // A partial symbol
+        assert_eq!(
+            Err(Error::InputLength),
+            ThreeOutOfSix::decode(&mut decode_buf, &encode_buf[..23])
+        );
+        assert_eq!(
+            Ok(2),
+            ThreeOutOfSix::decode(&mut decode_buf, &encode_buf[..24])
+        );
+    }
+
+    #[test]
+    pub fn decode_rejects_insufficient_capacity() {
+        let mut encode_buf = bitarr![u8, Msb0; 0; 24];
+        ThreeOutOfSix::encode(&mut encode_buf, &[0x12, 0x34]).unwrap();
+
+        let mut decode_buf = [0; 1];
+        assert_eq!(
+            Err(Error::Capacity),
+            ThreeOutOfSix::decode(&mut decode_buf, &encode_buf[..24])
+        );
+    }
 }
@@ -0,0 +1,157 @@
+use heapless::Vec;
+
+use crate::{stack::Packet, WMBusAddress};
+
+/// The authentication and fragmentation layer CI
+pub const AFL_CI: u8 = 0x90;
+
+/// Fragmentation control field bits
+const FCL_MCLP: u16 = 1 << 13;
+const FCL_MCRP: u16 = 1 << 11;
+const FCL_KIP: u16 = 1 << 9;
+
+/// Authentication and fragmentation layer fields, i.e. the data following the AFL CI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AflFields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AflFields<'a> {
+    /// Create the fields from the data following the AFL CI, starting with the AFL length
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Get the fragmentation control field
+    pub fn fcl(&self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.data.get(1..3)?.try_into().unwrap()))
+    }
+
+    /// Get the message counter, if present.
+    /// The counter is incremented by the meter for each message, and can be used to detect replayed messages, see [`ReplayFilter`].
+    pub fn message_counter(&self) -> Option<u32> {
+        let fcl = self.fcl()?;
+        if fcl & FCL_MCRP == 0 {
+            return None;
+        }
+
+        // The counter follows the message control field and the key information field
+        let mut offset = 3;
+        if fcl & FCL_MCLP != 0 {
+            offset += 1;
+        }
+        if fcl & FCL_KIP != 0 {
+            offset += 2;
+        }
+        let counter = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(counter.try_into().unwrap()))
+    }
+}
+
+/// Filter that detects replayed messages by the AFL message counter of up to `N` meters.
+/// When the filter is full, the meter that was seen first is forgotten.
+pub struct ReplayFilter<const N: usize> {
+    counters: Vec<(WMBusAddress, u32), N>,
+}
+
+impl<const N: usize> ReplayFilter<N> {
+    pub const fn new() -> Self {
+        Self {
+            counters: Vec::new(),
+        }
+    }
+
+    /// Get whether the packet is a replay, i.e. its AFL message counter does not increase from that of the previous packet from the same meter.
+    /// Packets without a message counter are never considered replays.
+    pub fn is_replay<const M: usize>(&mut self, packet: &Packet<M>) -> bool {
+        let Some(counter) = packet.afl().and_then(|afl| afl.message_counter()) else {
+            return false;
+        };
+        let Some(address) = packet
+            .sub_identification()
+            .or_else(|| packet.dll.as_ref().map(|dll| dll.address.clone()))
+        else {
+            return false;
+        };
+
+        if let Some((_, previous)) = self.counters.iter_mut().find(|(x, _)| *x == address) {
+            if counter <= *previous {
+                return true;
+            }
+            *previous = counter;
+            return false;
+        }
+
+        if self.counters.is_full() {
+            self.counters.remove(0);
+        }
+        self.counters.push((address, counter)).ok().unwrap();
+        false
+    }
+}
+
+impl<const N: usize> Default for ReplayFilter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        stack::{dll::DllFields, Mode},
+        DeviceType, ManufacturerCode,
+    };
+
+    use super::*;
+
+    fn packet(serial_number: u32, counter: u32) -> Packet {
+        let mut packet = Packet::new(Mode::ModeCFFB);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, serial_number, 0x01, DeviceType::Heat),
+        ));
+        // AFL with message control and message counter, followed by a short transport layer header
+        packet
+            .apl
+            .extend_from_slice(&[AFL_CI, 0x07, 0x00, 0x28, 0x05])
+            .unwrap();
+        packet
+            .apl
+            .extend_from_slice(&counter.to_le_bytes())
+            .unwrap();
+        packet
+            .apl
+            .extend_from_slice(&[0x7A, 0x01, 0x00, 0x00, 0x00])
+            .unwrap();
+        packet
+    }
+
+    #[test]
+    fn can_read_message_counter() {
+        let packet = packet(12345678, 0x01020304);
+        let afl = packet.afl().unwrap();
+        assert_eq!(Some(0x2800), afl.fcl());
+        assert_eq!(Some(0x01020304), afl.message_counter());
+        assert_eq!(Some(0x7A), packet.application().map(|(ci, _)| ci));
+
+        // Without the message counter present bit
+        assert_eq!(
+            None,
+            AflFields::new(&[0x03, 0x00, 0x20, 0x05]).message_counter()
+        );
+    }
+
+    #[test]
+    fn can_detect_replayed_counter() {
+        let mut filter = ReplayFilter::<1>::new();
+        assert!(!filter.is_replay(&packet(12345678, 1)));
+        assert!(!filter.is_replay(&packet(12345678, 2)));
+        assert!(filter.is_replay(&packet(12345678, 2)));
+        assert!(filter.is_replay(&packet(12345678, 1)));
+
+        // The first meter is forgotten when the filter is full
+        assert!(!filter.is_replay(&packet(87654321, 1)));
+        assert!(!filter.is_replay(&packet(12345678, 1)));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::WMBusAddress;
+
+use super::{DataRecords, Error};
+
+/// Iterator over the per-meter sub-frames of a data concentrator payload.
+/// Each sub-frame is a length byte, the eight byte address of the meter, and the data records of the meter,
+/// where the length is the number of bytes following the length byte.
+pub struct SubFrames<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> SubFrames<'a> {
+    /// Create the iterator from the data following the concentrator CI
+    pub const fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<'a> Iterator for SubFrames<'a> {
+    type Item = Result<(WMBusAddress, DataRecords<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&length, rest) = self.buffer.split_first()?;
+        let length = length as usize;
+        if length < 8 || rest.len() < length {
+            self.buffer = &[];
+            return Some(Err(Error::SubFrame));
+        }
+
+        let (sub_frame, next) = rest.split_at(length);
+        self.buffer = next;
+        let Ok(address) = WMBusAddress::from_bytes(sub_frame[..8].try_into().unwrap()) else {
+            return Some(Err(Error::SubFrame));
+        };
+        Some(Ok((address, DataRecords::new(&sub_frame[8..]))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_reject_truncated_sub_frame() {
+        let mut sub_frames = SubFrames::new(&[0x0A, 0x2D, 0x2C, 0x78, 0x56]);
+        assert_eq!(
+            Some(Err(Error::SubFrame)),
+            sub_frames.next().map(|x| x.map(|_| ()))
+        );
+        assert!(sub_frames.next().is_none());
+    }
+}
@@ -0,0 +1,503 @@
+mod afl;
+mod concentrator;
+pub mod records;
+
+use super::{tpl::TplFields, Layer, Packet, ReadError, WriteError};
+use crate::WMBusAddress;
+use bytes::BufMut;
+use core::cell::RefCell;
+use heapless::Vec;
+
+pub use afl::{AflFields, ReplayFilter, AFL_CI};
+pub use concentrator::SubFrames;
+pub use records::{
+    parse_records, DataRecord, DataRecordError, DataRecords, Function, ManufacturerHandler,
+    ParseError, Quantity, RecordValue,
+};
+
+/// The default minimum length of the data following an application CI
+pub const DEFAULT_MIN_DATA_LENGTHS: &[(u8, usize)] = &[
+    // Short transport layer header, i.e. ACC, STS, CW
+    (0x5A, 4),
+    (0x7A, 4),
+    // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+    (0x5B, 12),
+    (0x72, 12),
+];
+
+/// The application status bits of the transport layer status byte
+pub const STATUS_APPLICATION_MASK: u8 = 0x03;
+/// The application status of a meter that is busy, i.e. no data is available
+pub const STATUS_APPLICATION_BUSY: u8 = 0x01;
+
+/// The security mode bits of the transport layer configuration field
+pub const CONFIG_SECURITY_MODE_MASK: u16 = 0x1F00;
+
+/// Application Layer
+pub struct Apl {
+    min_data_lengths: &'static [(u8, usize)],
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The data following the application CI is shorter than the minimum length for the CI
+    TooShort(u8),
+    /// A sub-frame of a data concentrator payload is truncated or has an invalid address
+    SubFrame,
+    /// A data record could not be parsed while streaming it to a record sink
+    Record(DataRecordError),
+}
+
+/// Errors when decrypting the application layer
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecryptError {
+    /// The application layer is not encrypted with a supported security mode
+    NotEncrypted,
+    /// The encrypted part is shorter than announced by the configuration field
+    Incomplete,
+    /// The decrypted data does not start with the 0x2F 0x2F filler
+    WrongKey,
+}
+
+impl From<Error> for ReadError {
+    fn from(value: Error) -> Self {
+        ReadError::Apl(value)
+    }
+}
+
+impl Apl {
+    pub const fn new() -> Self {
+        Self::with_min_data_lengths(DEFAULT_MIN_DATA_LENGTHS)
+    }
+
+    /// Create an application layer that validates the minimum length of the data following the application CI.
+    /// Each entry is the CI and the minimum number of bytes that must follow it.
+    pub const fn with_min_data_lengths(min_data_lengths: &'static [(u8, usize)]) -> Self {
+        Self { min_data_lengths }
+    }
+
+    /// Create an application layer that calls `sink` for each data record while reading.
+    /// The records are parsed one at a time as the payload is walked, and neither the records nor the payload are stored in the packet,
+    /// i.e. [`Packet::apl`] is empty. See [`super::Stack::from_phl()`] for how to use it in a stack.
+    pub const fn with_record_sink<F: FnMut(&DataRecord)>(sink: F) -> RecordSink<F> {
+        RecordSink {
+            apl: Self::new(),
+            sink: RefCell::new(sink),
+        }
+    }
+}
+
+/// Application layer that streams the data records to a sink, see [`Apl::with_record_sink()`]
+pub struct RecordSink<F> {
+    apl: Apl,
+    sink: RefCell<F>,
+}
+
+impl<F: FnMut(&DataRecord)> Layer for RecordSink<F> {
+    fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+        self.apl.check_min_data_length(buffer)?;
+
+        let acknowledgement = packet
+            .dll
+            .as_ref()
+            .is_some_and(|dll| dll.is_acknowledgement());
+        if let Some(records) = data_records(packet.tpl.as_ref(), acknowledgement, buffer) {
+            let mut sink = self.sink.borrow_mut();
+            for record in records {
+                sink(&record.map_err(Error::Record)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn write<const N: usize>(
+        &self,
+        writer: &mut impl BufMut,
+        packet: &Packet<N>,
+    ) -> Result<(), WriteError> {
+        self.apl.write(writer, packet)
+    }
+}
+
+impl Default for Apl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the chain of control information (CI) fields in the application layer.
+/// A header CI, such as the authentication and fragmentation layer, is followed by another CI,
+/// and the chain ends with the CI that identifies the application data.
+/// Each item is the CI together with the bytes that follow it up until the next CI in the chain.
+pub struct CiChain<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> CiChain<'a> {
+    pub const fn new(apl: &'a [u8]) -> Self {
+        Self { buffer: apl }
+    }
+}
+
+impl<'a> Iterator for CiChain<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&ci, rest) = self.buffer.split_first()?;
+        let length = match header_length(ci, rest) {
+            Some(length) if length <= rest.len() => length,
+            _ => rest.len(),
+        };
+        let (data, next) = rest.split_at(length);
+        self.buffer = next;
+        Some((ci, data))
+    }
+}
+
+/// Get the length of the header following a CI that is succeeded by another CI
+fn header_length(ci: u8, buffer: &[u8]) -> Option<usize> {
+    match ci {
+        // Authentication and fragmentation layer, the first byte is the AFL length
+        AFL_CI => buffer.first().map(|&len| 1 + len as usize),
+        _ => None,
+    }
+}
+
+/// Get whether the CI is manufacturer specific.
+/// The data following the CI has no standard structure, and is passed through untouched.
+pub const fn is_manufacturer_specific(ci: u8) -> bool {
+    matches!(ci, 0xA0..=0xB7)
+}
+
+/// Get whether the application CI is an OMS compact profile,
+/// where the records are encoded as differences to a reference and cannot be parsed as standard data records
+pub const fn is_compact_profile(ci: u8) -> bool {
+    matches!(ci, 0x69 | 0x6A)
+}
+
+/// Get whether the application CI is a transport layer header without application data, as used for acknowledgements
+pub const fn is_acknowledgement(ci: u8) -> bool {
+    matches!(ci, 0x8A | 0x8B)
+}
+
+/// Get the offset of the data records in the data following an application CI that carries data records
+pub(crate) const fn records_offset(ci: u8) -> Option<usize> {
+    match ci {
+        // No transport layer header
+        0x78 => Some(0),
+        // Short transport layer header, i.e. ACC, STS, CW
+        0x7A => Some(4),
+        // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+        0x72 => Some(12),
+        _ => None,
+    }
+}
+
+/// Get the data records of the unencrypted application layer `apl`, see [`Packet::data_records()`].
+/// `tpl` is the transport layer header if it is already removed from `apl`.
+pub(crate) fn data_records<'a>(
+    tpl: Option<&TplFields>,
+    acknowledgement: bool,
+    apl: &'a [u8],
+) -> Option<DataRecords<'a>> {
+    let application = CiChain::new(apl).last();
+    if acknowledgement || application.is_some_and(|(ci, _)| is_acknowledgement(ci)) {
+        return Some(DataRecords::new(&[]));
+    }
+    if let Some(tpl) = tpl {
+        return tpl
+            .cw()
+            .and_then(|cw| encrypted_part(cw, apl))
+            .is_none()
+            .then(|| DataRecords::new(apl));
+    }
+    let (ci, data) = application?;
+    if ciphertext(ci, data).is_some() {
+        return None;
+    }
+    let offset = records_offset(ci)?;
+    data.get(offset..).map(DataRecords::new)
+}
+
+/// Get the meter address in the long transport layer header of the data following an application CI, if any
+pub(crate) fn long_header_address(ci: u8, data: &[u8]) -> Option<WMBusAddress> {
+    match ci {
+        // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+        0x5B | 0x72 | 0x8B if data.len() >= 8 => {
+            // The address has the same fields as the data link layer address, but the serial number comes first
+            let mut bytes = [0; 8];
+            bytes[..2].copy_from_slice(&data[4..6]);
+            bytes[2..6].copy_from_slice(&data[..4]);
+            bytes[6..].copy_from_slice(&data[6..8]);
+            WMBusAddress::from_bytes(bytes).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Get the length of the transport layer header in the data following an application CI that carries data records
+pub(crate) const fn transport_header_length(ci: u8) -> Option<usize> {
+    match ci {
+        // Short transport layer header, i.e. ACC, STS, CW
+        0x5A | 0x7A => Some(4),
+        // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+        0x5B | 0x72 => Some(12),
+        _ => None,
+    }
+}
+
+/// Get the access number in the transport layer header of the data following an application CI, if any
+pub(crate) fn access_number(ci: u8, data: &[u8]) -> Option<u8> {
+    match ci {
+        // Short transport layer header, i.e. ACC, STS, CW
+        0x5A | 0x7A | 0x8A => data.first().copied(),
+        // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+        0x5B | 0x72 | 0x8B => data.get(8).copied(),
+        _ => None,
+    }
+}
+
+/// Get the status byte in the transport layer header of the data following an application CI, if any
+pub(crate) fn status(ci: u8, data: &[u8]) -> Option<u8> {
+    match ci {
+        // Short transport layer header, i.e. ACC, STS, CW
+        0x5A | 0x7A | 0x8A => data.get(1).copied(),
+        // Long transport layer header, i.e. ID, M, V, T, ACC, STS, CW
+        0x5B | 0x72 | 0x8B => data.get(9).copied(),
+        _ => None,
+    }
+}
+
+/// Get the encrypted part of the data following an application CI.
+/// The encrypted part is delimited by the security mode and the number of encrypted blocks in the transport layer configuration field.
+/// Only security modes 5 and 7 are supported.
+pub(crate) fn ciphertext(ci: u8, data: &[u8]) -> Option<&[u8]> {
+    // The configuration field ends the transport layer header
+    let config_offset = transport_header_length(ci)? - 2;
+
+    let config = u16::from_le_bytes(
+        data.get(config_offset..config_offset + 2)?
+            .try_into()
+            .unwrap(),
+    );
+    encrypted_part(config, &data[config_offset + 2..])
+}
+
+/// Get the encrypted part of the data following a transport layer header with the configuration field `config`
+pub(crate) fn encrypted_part(config: u16, data: &[u8]) -> Option<&[u8]> {
+    let security_mode = (config & CONFIG_SECURITY_MODE_MASK) >> 8;
+    let encrypted_blocks = ((config >> 4) & 0x0F) as usize;
+    let header_length = match security_mode {
+        5 => 0,
+        // Mode 7 has a configuration field extension
+        7 => 1,
+        _ => return None,
+    };
+
+    if encrypted_blocks == 0 {
+        return None;
+    }
+
+    data.get(header_length..header_length + 16 * encrypted_blocks)
+}
+
+/// Decrypt the data following a transport layer header in place, if it is encrypted with AES-128-CBC in security mode 5.
+/// The initialization vector is the meter `address` followed by the access number repeated 8 times.
+/// The trailing 0x2F fillers of the decrypted part are removed by moving any unencrypted data down, and the new length of `data` is returned.
+#[cfg(feature = "crypto")]
+pub(crate) fn decrypt_mode5(
+    key: &[u8; 16],
+    address: &[u8; 8],
+    acc: u8,
+    config: u16,
+    data: &mut [u8],
+) -> Result<usize, DecryptError> {
+    use aes::cipher::{BlockDecrypt, KeyInit};
+
+    let security_mode = (config & CONFIG_SECURITY_MODE_MASK) >> 8;
+    let encrypted_blocks = ((config >> 4) & 0x0F) as usize;
+    if security_mode != 5 || encrypted_blocks == 0 {
+        return Err(DecryptError::NotEncrypted);
+    }
+    let encrypted_length = 16 * encrypted_blocks;
+    let encrypted = data
+        .get_mut(..encrypted_length)
+        .ok_or(DecryptError::Incomplete)?;
+
+    let cipher = aes::Aes128::new(key.into());
+    let mut iv = [acc; 16];
+    iv[..8].copy_from_slice(address);
+
+    // Verify the key on a copy of the first block, so that the data is left encrypted if the key is wrong
+    let mut first: [u8; 16] = encrypted[..16].try_into().unwrap();
+    cipher.decrypt_block((&mut first).into());
+    if first[0] ^ iv[0] != 0x2F || first[1] ^ iv[1] != 0x2F {
+        return Err(DecryptError::WrongKey);
+    }
+
+    let mut previous = iv;
+    for block in encrypted.chunks_exact_mut(16) {
+        let ciphertext: [u8; 16] = block.try_into().unwrap();
+        cipher.decrypt_block(block.into());
+        block
+            .iter_mut()
+            .zip(previous)
+            .for_each(|(byte, previous)| *byte ^= previous);
+        previous = ciphertext;
+    }
+
+    // Keep the leading 0x2F 0x2F that verified the key
+    let fillers = encrypted[2..]
+        .iter()
+        .rev()
+        .take_while(|&&byte| byte == 0x2F)
+        .count();
+    let decrypted_length = encrypted_length - fillers;
+    data.copy_within(encrypted_length.., decrypted_length);
+    Ok(data.len() - fillers)
+}
+
+impl Apl {
+    fn check_min_data_length(&self, buffer: &[u8]) -> Result<(), Error> {
+        let manufacturer_specific = buffer
+            .first()
+            .is_some_and(|&ci| is_manufacturer_specific(ci));
+        if let Some((ci, data)) = CiChain::new(buffer)
+            .last()
+            .filter(|_| !manufacturer_specific)
+        {
+            let min_length = self
+                .min_data_lengths
+                .iter()
+                .find_map(|&(x, min_length)| (x == ci).then_some(min_length));
+            if min_length.is_some_and(|min_length| data.len() < min_length) {
+                return Err(Error::TooShort(ci));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Layer for Apl {
+    fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+        self.check_min_data_length(buffer)?;
+
+        packet.apl = Vec::from_slice(buffer).map_err(|_| ReadError::Capacity)?;
+        Ok(())
+    }
+
+    fn write<const N: usize>(
+        &self,
+        writer: &mut impl BufMut,
+        packet: &Packet<N>,
+    ) -> Result<(), WriteError> {
+        if writer.remaining_mut() < packet.apl.len() {
+            return Err(WriteError::BufferTooSmall);
+        }
+        writer.put_slice(&packet.apl);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use crate::stack::Mode;
+
+    use super::*;
+
+    #[test]
+    fn can_stream_records_to_sink() {
+        let count = Cell::new(0);
+        let apl = Apl::with_record_sink(|_| count.set(count.get() + 1));
+        #[rustfmt::skip]
+        let buffer = [
+            0x7A, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x13, 0x01, 0x00, 0x00, 0x00,
+            0x02, 0x5A, 0x10, 0x00,
+            0x2F, 0x2F,
+        ];
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        apl.read(&mut packet, &buffer).unwrap();
+        assert_eq!(2, count.get());
+        assert!(packet.apl.is_empty());
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        assert_eq!(
+            Err(ReadError::Apl(Error::Record(DataRecordError::Incomplete))),
+            apl.read(&mut packet, &buffer[..13])
+        );
+        assert_eq!(3, count.get());
+    }
+
+    #[test]
+    fn can_iterate_single_ci() {
+        let apl = [0x7A, 0x01, 0x02, 0x03];
+        let mut chain = CiChain::new(&apl);
+        assert_eq!(Some((0x7A, [0x01, 0x02, 0x03].as_slice())), chain.next());
+        assert_eq!(None, chain.next());
+    }
+
+    #[test]
+    fn can_iterate_primary_and_secondary_ci() {
+        let apl = [0x90, 0x02, 0xAA, 0xBB, 0x7A, 0x01, 0x02, 0x03];
+        let mut chain = CiChain::new(&apl);
+        assert_eq!(Some((0x90, [0x02, 0xAA, 0xBB].as_slice())), chain.next());
+        assert_eq!(Some((0x7A, [0x01, 0x02, 0x03].as_slice())), chain.next());
+        assert_eq!(None, chain.next());
+    }
+
+    #[test]
+    fn can_get_ciphertext() {
+        let mut data = [0; 4 + 2 * 16 + 2];
+        data[..4].copy_from_slice(&[0x01, 0x00, 0x20, 0x05]);
+        data[4..].fill(0xAA);
+        assert_eq!(Some(&data[4..36]), ciphertext(0x7A, &data));
+
+        // Unencrypted
+        data[3] = 0x00;
+        assert_eq!(None, ciphertext(0x7A, &data));
+
+        // Truncated
+        data[3] = 0x05;
+        assert_eq!(None, ciphertext(0x7A, &data[..35]));
+    }
+
+    #[test]
+    fn rejects_too_short_application_data() {
+        let apl = Apl::new();
+        let mut packet: Packet = Packet::new(crate::stack::Mode::ModeCFFB);
+
+        assert_eq!(
+            Err(ReadError::Apl(Error::TooShort(0x7A))),
+            apl.read(&mut packet, &[0x7A, 0x01, 0x00, 0x00])
+        );
+        assert_eq!(
+            Ok(()),
+            apl.read(&mut packet, &[0x7A, 0x01, 0x00, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(ReadError::Apl(Error::TooShort(0x7A))),
+            apl.read(&mut packet, &[0x90, 0x01, 0xAA, 0x7A, 0x01])
+        );
+
+        // CI's without a minimum length are not validated
+        assert_eq!(Ok(()), apl.read(&mut packet, &[0x78]));
+        assert_eq!(
+            Ok(()),
+            Apl::with_min_data_lengths(&[]).read(&mut packet, &[0x7A])
+        );
+    }
+
+    #[test]
+    fn can_iterate_truncated_header() {
+        let apl = [0x90, 0x04, 0xAA];
+        let mut chain = CiChain::new(&apl);
+        assert_eq!(Some((0x90, [0x04, 0xAA].as_slice())), chain.next());
+        assert_eq!(None, chain.next());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{DeviceType, ManufacturerCode, WMBusAddress};
+
+use super::{DataRecordError, DataRecords, Quantity};
+
+/// The current readings of an Apator water meter
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ApatorReading {
+    /// The accumulated volume in m3
+    pub volume: Option<f64>,
+    /// The status byte, i.e. the first byte of the manufacturer specific data
+    pub status: Option<u8>,
+}
+
+/// Get whether the address is that of an Apator water meter
+pub fn is_apator_water(address: &WMBusAddress) -> bool {
+    address.manufacturer_code() == ManufacturerCode::APT
+        && matches!(
+            address.device_type(),
+            Some(DeviceType::Water | DeviceType::ColdWater | DeviceType::WarmWater)
+        )
+}
+
+/// Decode the data records of an Apator water meter.
+/// `None` is returned if the address is not that of an Apator water meter.
+pub fn decode(
+    address: &WMBusAddress,
+    records: DataRecords<'_>,
+) -> Option<Result<ApatorReading, DataRecordError>> {
+    is_apator_water(address).then(|| ApatorReading::from_records(records))
+}
+
+impl ApatorReading {
+    /// Decode the current readings from the data records.
+    /// The volume register is the current volume, and the status is sent as manufacturer specific data following the records.
+    pub fn from_records(mut records: DataRecords<'_>) -> Result<Self, DataRecordError> {
+        let mut reading = Self::default();
+
+        for record in records.by_ref() {
+            let record = record?;
+            if !record.dib.is_current_value() {
+                continue;
+            }
+
+            if let (Some(info), Some((value, _))) =
+                (record.value_information(), record.scaled_value())
+            {
+                if info.quantity == Quantity::Volume {
+                    reading.volume = Some(value);
+                }
+            }
+        }
+
+        reading.status = records
+            .manufacturer_specific()
+            .and_then(|data| data.first().copied());
+
+        Ok(reading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stack::{Mode, Stack};
+
+    use super::*;
+
+    #[test]
+    fn can_decode_apator_water() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x18, 0x44, 0x14, 0x86, 0x78, 0x56, 0x34, 0x12, 0x04, 0x07,
+            0x7A, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x13, 0xD2, 0x04, 0x00, 0x00, // Volume 1.234m3
+            0x0F, 0x02, // Manufacturer specific status
+            0xBE, 0xDE,
+        ];
+        let packet = Stack::new().read(&frame, Mode::ModeCFFB).unwrap();
+        let address = &packet.dll.as_ref().unwrap().address;
+
+        // When
+        let reading = decode(address, packet.data_records().unwrap())
+            .unwrap()
+            .unwrap();
+
+        // Then
+        assert_eq!(Some(1.234), reading.volume);
+        assert_eq!(Some(0x02), reading.status);
+    }
+
+    #[test]
+    fn can_ignore_other_meters() {
+        let address = WMBusAddress::new(ManufacturerCode::APT, 12345678, 0x04, DeviceType::Heat);
+        assert!(decode(&address, DataRecords::new(&[])).is_none());
+    }
+}
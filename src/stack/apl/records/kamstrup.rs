@@ -0,0 +1,142 @@
+use crate::{DeviceType, ManufacturerCode, WMBusAddress};
+
+use super::{DataRecordError, DataRecords, Quantity, RecordValue, Unit};
+
+/// Kamstrup manufacturer specific VIFE for the forward energy, i.e. m3 x forward temperature
+const VIFE_FORWARD_ENERGY: u8 = 0x07;
+/// Kamstrup manufacturer specific VIFE for the return energy, i.e. m3 x return temperature
+const VIFE_RETURN_ENERGY: u8 = 0x08;
+/// Kamstrup manufacturer specific VIFE for the info codes
+const VIFE_INFO_CODES: u8 = 0x21;
+
+/// The current readings of a Kamstrup Multical heat meter
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MulticalReading {
+    /// The accumulated energy in the returned unit
+    pub energy: Option<(f64, Unit)>,
+    /// The accumulated volume in m3
+    pub volume: Option<f64>,
+    /// The flow temperature in C
+    pub flow_temperature: Option<f64>,
+    /// The return temperature in C
+    pub return_temperature: Option<f64>,
+    /// The accumulated forward energy in m3 x C
+    pub forward_energy: Option<i64>,
+    /// The accumulated return energy in m3 x C
+    pub return_energy: Option<i64>,
+    /// The info codes
+    pub info_codes: Option<u32>,
+}
+
+/// Get whether the address is that of a Kamstrup Multical heat or cooling meter.
+/// The meters are identified by their device type, as the version differs between the Multical generations.
+pub fn is_multical(address: &WMBusAddress) -> bool {
+    address.manufacturer_code() == ManufacturerCode::KAM
+        && matches!(
+            address.device_type(),
+            Some(
+                DeviceType::Heat
+                    | DeviceType::HeatInlet
+                    | DeviceType::Cooling
+                    | DeviceType::CoolingInlet
+                    | DeviceType::HeatCooling
+            )
+        )
+}
+
+/// Decode the data records of a Kamstrup Multical heat meter.
+/// `None` is returned if the address is not that of a Multical meter.
+pub fn decode(
+    address: &WMBusAddress,
+    records: DataRecords<'_>,
+) -> Option<Result<MulticalReading, DataRecordError>> {
+    is_multical(address).then(|| MulticalReading::from_records(records))
+}
+
+impl MulticalReading {
+    /// Decode the current readings from the data records.
+    /// Only current values are considered, as the meter also reports e.g. the maximum flow temperature with the same VIF.
+    pub fn from_records(records: DataRecords<'_>) -> Result<Self, DataRecordError> {
+        let mut reading = Self::default();
+
+        for record in records {
+            let record = record?;
+            if !record.dib.is_current_value() {
+                continue;
+            }
+
+            if record.vib.vif == 0xFF {
+                // Manufacturer specific VIF
+                let RecordValue::Integer(value) = record.value() else {
+                    continue;
+                };
+                match record.vib.vife.first().map(|vife| vife & 0x7F) {
+                    Some(VIFE_FORWARD_ENERGY) => reading.forward_energy = Some(value),
+                    Some(VIFE_RETURN_ENERGY) => reading.return_energy = Some(value),
+                    Some(VIFE_INFO_CODES) => reading.info_codes = Some(value as u32),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let (Some(info), Some((value, unit))) =
+                (record.value_information(), record.scaled_value())
+            else {
+                continue;
+            };
+            match info.quantity {
+                Quantity::Energy => reading.energy = Some((value, unit)),
+                Quantity::Volume => reading.volume = Some(value),
+                Quantity::FlowTemperature => reading.flow_temperature = Some(value),
+                Quantity::ReturnTemperature => reading.return_temperature = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(reading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_multical_heat() {
+        // Given
+        let address = WMBusAddress::new(ManufacturerCode::KAM, 67042798, 0x30, DeviceType::Heat);
+        #[rustfmt::skip]
+        let payload = [
+            0x2F, 0x2F,
+            0x04, 0x06, 0xA4, 0x1A, 0x00, 0x00, // Energy 6820kWh
+            0x04, 0x14, 0x49, 0xAD, 0x00, 0x00, // Volume 443.61m3
+            0x04, 0xFF, 0x07, 0x4D, 0x6E, 0x03, 0x00, // Forward energy
+            0x04, 0xFF, 0x08, 0x8A, 0x4E, 0x02, 0x00, // Return energy
+            0x02, 0x59, 0x2B, 0x17, // Flow temperature 59.31C
+            0x02, 0x5D, 0xE3, 0x0E, // Return temperature 38.11C
+            0x12, 0x59, 0x10, 0x27, // Maximum flow temperature 100.00C
+            0x01, 0xFF, 0x21, 0x00, // Info codes
+        ];
+
+        // When
+        let reading = decode(&address, DataRecords::new(&payload))
+            .unwrap()
+            .unwrap();
+
+        // Then
+        assert_eq!(Some((6820000.0, Unit::WattHour)), reading.energy);
+        assert_eq!(Some(443.61), reading.volume);
+        assert_eq!(Some(59.31), reading.flow_temperature);
+        assert_eq!(Some(38.11), reading.return_temperature);
+        assert_eq!(Some(0x036E4D), reading.forward_energy);
+        assert_eq!(Some(0x024E8A), reading.return_energy);
+        assert_eq!(Some(0), reading.info_codes);
+    }
+
+    #[test]
+    fn can_ignore_other_meters() {
+        let address =
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x1B, DeviceType::ColdWater);
+        assert!(decode(&address, DataRecords::new(&[])).is_none());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{DeviceType, ManufacturerCode, WMBusAddress};
+
+use super::{DataRecordError, DataRecords, Quantity, Unit};
+
+/// The VIFE for the accumulation of negative contributions only, i.e. the exported energy
+const VIFE_BACKWARD_FLOW: u8 = 0x3C;
+
+/// The current readings of a Landis+Gyr electricity meter
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LandisGyrReading {
+    /// The accumulated active energy import in Wh
+    pub energy_import: Option<f64>,
+    /// The accumulated active energy export in Wh
+    pub energy_export: Option<f64>,
+    /// The instantaneous active power in W
+    pub power: Option<f64>,
+}
+
+/// Get whether the address is that of a Landis+Gyr electricity meter
+pub fn is_landisgyr_electricity(address: &WMBusAddress) -> bool {
+    address.manufacturer_code() == ManufacturerCode::LUG
+        && address.device_type() == Some(DeviceType::Electricity)
+}
+
+/// Decode the data records of a Landis+Gyr electricity meter.
+/// `None` is returned if the address is not that of a Landis+Gyr electricity meter.
+pub fn decode(
+    address: &WMBusAddress,
+    records: DataRecords<'_>,
+) -> Option<Result<LandisGyrReading, DataRecordError>> {
+    is_landisgyr_electricity(address).then(|| LandisGyrReading::from_records(records))
+}
+
+impl LandisGyrReading {
+    /// Decode the current readings from the data records.
+    /// The import and export registers share the energy VIF, where the export register has the backward flow VIFE.
+    pub fn from_records(records: DataRecords<'_>) -> Result<Self, DataRecordError> {
+        let mut reading = Self::default();
+
+        for record in records {
+            let record = record?;
+            if !record.dib.is_current_value() {
+                continue;
+            }
+
+            let (Some(info), Some((value, unit))) =
+                (record.value_information(), record.scaled_value())
+            else {
+                continue;
+            };
+            let export = record
+                .vib
+                .vife
+                .iter()
+                .any(|vife| vife & 0x7F == VIFE_BACKWARD_FLOW);
+            match (info.quantity, unit) {
+                (Quantity::Energy, Unit::WattHour) if export => reading.energy_export = Some(value),
+                (Quantity::Energy, Unit::WattHour) => reading.energy_import = Some(value),
+                (Quantity::Power, Unit::Watt) => reading.power = Some(value),
+                _ => {}
+            }
+        }
+
+        Ok(reading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stack::{Mode, Stack};
+
+    use super::*;
+
+    #[test]
+    fn can_decode_landisgyr_electricity() {
+        // Given
+        #[rustfmt::skip]
+        let frame = [
+            0x23, 0x44, 0xA7, 0x32, 0x78, 0x56, 0x34, 0x12, 0x01, 0x02,
+            0x7A, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x04, 0x39, 0x30, 0x00, 0x00, // Energy import 123450Wh
+            0x04, 0x84, 0x3C, 0x10, 0x27, 0x00, 0x00, // Energy export 100000Wh
+            0x04, 0x2B, 0x2C, 0x01, 0x00, 0x00, // Power 300W
+            0xF1, 0xF9,
+        ];
+        let packet = Stack::new().read(&frame, Mode::ModeCFFB).unwrap();
+        let address = &packet.dll.as_ref().unwrap().address;
+
+        // When
+        let reading = decode(address, packet.data_records().unwrap())
+            .unwrap()
+            .unwrap();
+
+        // Then
+        assert_eq!(Some(123450.0), reading.energy_import);
+        assert_eq!(Some(100000.0), reading.energy_export);
+        assert_eq!(Some(300.0), reading.power);
+    }
+
+    #[test]
+    fn can_ignore_other_meters() {
+        let address = WMBusAddress::new(ManufacturerCode::LUG, 12345678, 0x01, DeviceType::Heat);
+        assert!(decode(&address, DataRecords::new(&[])).is_none());
+    }
+}
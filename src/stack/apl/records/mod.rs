@@ -0,0 +1,1075 @@
+#[cfg(feature = "vendor-apator")]
+pub mod apator;
+pub mod kamstrup;
+#[cfg(feature = "vendor-lug")]
+pub mod landisgyr;
+mod reading;
+mod registry;
+mod time;
+
+use heapless::Vec;
+
+use crate::ManufacturerCode;
+
+pub use reading::MeterReading;
+pub use registry::{ManufacturerHandler, VifHandler, VifRegistry};
+pub use time::DateTime;
+
+/// The maximum number of DIFE's according to EN13757-3
+pub const DIFE_MAX: usize = 10;
+/// The maximum number of VIFE's according to EN13757-3
+pub const VIFE_MAX: usize = 10;
+
+const EXTENSION_BIT: u8 = 0x80;
+/// The VIF denoting that the VIFE's are manufacturer specific
+const MANUFACTURER_SPECIFIC_VIF: u8 = 0x7F;
+/// The time point VIF for a data type G date
+const VIF_DATE: u8 = 0x6C;
+/// The time point VIF for a data type F or I date and time
+const VIF_DATE_TIME: u8 = 0x6D;
+/// The special function DIF that starts an extended status block.
+/// The DIF is reserved in EN13757-3, and the structure of the block is manufacturer specific.
+pub const EXTENDED_STATUS_DIF: u8 = 0x3F;
+
+/// Iterator over the data records in a data record payload
+#[derive(Clone)]
+pub struct DataRecords<'a> {
+    buffer: &'a [u8],
+    manufacturer_specific: Option<&'a [u8]>,
+    extended_status: Option<&'a [u8]>,
+    manufacturer: Option<ManufacturerCode>,
+    registry: VifRegistry<'a>,
+    handler: Option<&'a dyn ManufacturerHandler>,
+    quantity: Option<Quantity>,
+}
+
+/// A data record, i.e. a data information block, a value information block and the data
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRecord<'a> {
+    pub dib: DataInformationBlock,
+    pub vib: ValueInformationBlock,
+    pub data: &'a [u8],
+    raw: &'a [u8],
+    manufacturer_info: Option<ValueInformation>,
+}
+
+/// Data information block, i.e. the DIF and any DIFE's
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataInformationBlock {
+    pub dif: u8,
+    pub data_field: DataField,
+    pub function: Function,
+    pub storage_number: u64,
+    pub tariff: u32,
+    pub subunit: u16,
+}
+
+/// Value information block, i.e. the VIF and any VIFE's
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueInformationBlock {
+    pub vif: u8,
+    pub vife: Vec<u8, VIFE_MAX>,
+}
+
+/// The data field of the DIF
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataField {
+    NoData,
+    Integer8,
+    Integer16,
+    Integer24,
+    Integer32,
+    Real32,
+    Integer48,
+    Integer64,
+    SelectionForReadout,
+    Bcd2,
+    Bcd4,
+    Bcd6,
+    Bcd8,
+    VariableLength,
+    Bcd12,
+    SpecialFunction,
+}
+
+/// The function field of the DIF
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Function {
+    Instantaneous,
+    Maximum,
+    Minimum,
+    ValueDuringErrorState,
+}
+
+/// The value of a data record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordValue<'a> {
+    /// The record has no data
+    None,
+    /// A signed integer, decoded from either a two's complement binary or a BCD data field
+    Integer(i64),
+    /// An IEEE 754 real
+    Real(f32),
+    /// Data that is not numeric, e.g. BCD with invalid digits
+    Bytes(&'a [u8]),
+}
+
+/// The physical quantity of a value information field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Quantity {
+    Energy,
+    Volume,
+    Mass,
+    OnTime,
+    OperatingTime,
+    Power,
+    VolumeFlow,
+    MassFlow,
+    FlowTemperature,
+    ReturnTemperature,
+    TemperatureDifference,
+    ExternalTemperature,
+    Pressure,
+    TimePoint,
+    HcaUnits,
+    AveragingDuration,
+    ActualityDuration,
+    FabricationNumber,
+    EnhancedIdentification,
+    BusAddress,
+}
+
+/// The unit of a value information field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Unit {
+    WattHour,
+    Joule,
+    CubicMeter,
+    Kilogram,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Watt,
+    JoulePerHour,
+    CubicMeterPerHour,
+    CubicMeterPerMinute,
+    CubicMeterPerSecond,
+    KilogramPerHour,
+    Celsius,
+    Kelvin,
+    Bar,
+    None,
+}
+
+/// Decoded value information, i.e. the value is `value * 10^exponent` in `unit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ValueInformation {
+    pub quantity: Quantity,
+    pub unit: Unit,
+    pub exponent: i8,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataRecordError {
+    Incomplete,
+    /// The record has more DIFE's or VIFE's than allowed
+    Capacity,
+    /// The data field is not supported
+    UnsupportedDataField,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// There are more records than the capacity
+    TooManyRecords,
+    Record(DataRecordError),
+}
+
+impl From<DataRecordError> for ParseError {
+    fn from(value: DataRecordError) -> Self {
+        ParseError::Record(value)
+    }
+}
+
+/// Parse all data records in `payload` into a fixed capacity vector
+pub fn parse_records<const N: usize>(payload: &[u8]) -> Result<Vec<DataRecord<'_>, N>, ParseError> {
+    let mut records = Vec::new();
+    for record in DataRecords::new(payload) {
+        records
+            .push(record?)
+            .map_err(|_| ParseError::TooManyRecords)?;
+    }
+    Ok(records)
+}
+
+impl<'a> DataRecords<'a> {
+    /// Create a new iterator over the data records in `buffer`
+    pub const fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            manufacturer_specific: None,
+            extended_status: None,
+            manufacturer: None,
+            registry: VifRegistry::new(&[]),
+            handler: None,
+            quantity: None,
+        }
+    }
+
+    /// Create a new iterator over the data records in `buffer` sent by `manufacturer`,
+    /// where manufacturer specific VIF's are decoded using the handlers in `registry`
+    pub const fn with_registry(
+        buffer: &'a [u8],
+        manufacturer: ManufacturerCode,
+        registry: VifRegistry<'a>,
+    ) -> Self {
+        Self {
+            buffer,
+            manufacturer_specific: None,
+            extended_status: None,
+            manufacturer: Some(manufacturer),
+            registry,
+            handler: None,
+            quantity: None,
+        }
+    }
+
+    /// Only yield the records of `quantity`.
+    /// Other records are skipped without being yielded, and errors are still yielded.
+    pub const fn filter_quantity(mut self, quantity: Quantity) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    /// Decode manufacturer specific VIF's using the handler for `manufacturer` in `registry`, see [`DataRecords::with_registry()`]
+    pub const fn with_vif_registry(
+        mut self,
+        manufacturer: ManufacturerCode,
+        registry: VifRegistry<'a>,
+    ) -> Self {
+        self.manufacturer = Some(manufacturer);
+        self.registry = registry;
+        self
+    }
+
+    /// Continue parsing the data records in the manufacturer specific data sent by `manufacturer` using `handler`.
+    /// The manufacturer specific data is still available as raw bytes, also if the handler does not understand it.
+    pub const fn with_manufacturer_handler(
+        mut self,
+        manufacturer: ManufacturerCode,
+        handler: &'a dyn ManufacturerHandler,
+    ) -> Self {
+        self.manufacturer = Some(manufacturer);
+        self.handler = Some(handler);
+        self
+    }
+
+    /// Get the manufacturer specific data following the 0x0F or 0x1F DIF, if encountered during the iteration
+    pub const fn manufacturer_specific(&self) -> Option<&'a [u8]> {
+        self.manufacturer_specific
+    }
+
+    /// Get the extended status block following the [`EXTENDED_STATUS_DIF`], if encountered during the iteration
+    pub const fn extended_status(&self) -> Option<&'a [u8]> {
+        self.extended_status
+    }
+
+    fn read_record(&mut self) -> Result<Option<DataRecord<'a>>, DataRecordError> {
+        loop {
+            let record = self.read_any_record()?;
+            let skip = record.as_ref().is_some_and(|record| {
+                self.quantity
+                    .is_some_and(|quantity| record.quantity() != Some(quantity))
+            });
+            if !skip {
+                return Ok(record);
+            }
+        }
+    }
+
+    fn read_any_record(&mut self) -> Result<Option<DataRecord<'a>>, DataRecordError> {
+        // Skip idle fillers
+        let (start, dif) = loop {
+            let start = self.buffer;
+            let Some((&dif, rest)) = self.buffer.split_first() else {
+                return Ok(None);
+            };
+            self.buffer = rest;
+            if dif != 0x2F {
+                break (start, dif);
+            }
+        };
+
+        if dif == 0x0F || dif == 0x1F {
+            self.manufacturer_specific = Some(self.buffer);
+            // The handler is only used once, as the manufacturer specific data cannot be nested
+            let records = self
+                .handler
+                .take()
+                .zip(self.manufacturer)
+                .and_then(|(handler, manufacturer)| handler.records(manufacturer, self.buffer));
+            self.buffer = records.unwrap_or(&[]);
+            return self.read_any_record();
+        }
+
+        if dif == EXTENDED_STATUS_DIF {
+            self.extended_status = Some(self.buffer);
+            self.buffer = &[];
+            return Ok(None);
+        }
+
+        let dib = self.read_dib(dif)?;
+        let vib = self.read_vib()?;
+        let length = match dib.data_field {
+            DataField::VariableLength => self.read_lvar()?,
+            data_field => data_field
+                .data_length()
+                .ok_or(DataRecordError::UnsupportedDataField)?,
+        };
+        if self.buffer.len() < length {
+            return Err(DataRecordError::Incomplete);
+        }
+        let (data, rest) = self.buffer.split_at(length);
+        self.buffer = rest;
+        let manufacturer_info = if vib.vif & 0x7F == MANUFACTURER_SPECIFIC_VIF {
+            decode_vif_with_registry(vib.vif, &vib.vife, self.manufacturer, &self.registry)
+        } else {
+            None
+        };
+        let raw = &start[..start.len() - rest.len()];
+
+        Ok(Some(DataRecord {
+            dib,
+            vib,
+            data,
+            raw,
+            manufacturer_info,
+        }))
+    }
+
+    fn read_dib(&mut self, dif: u8) -> Result<DataInformationBlock, DataRecordError> {
+        let mut dib = DataInformationBlock {
+            dif,
+            data_field: DataField::from_dif(dif),
+            function: Function::from_dif(dif),
+            storage_number: ((dif >> 6) & 0x01) as u64,
+            tariff: 0,
+            subunit: 0,
+        };
+
+        let mut extension = dif & EXTENSION_BIT != 0;
+        let mut index = 0;
+        while extension {
+            if index == DIFE_MAX {
+                return Err(DataRecordError::Capacity);
+            }
+            let (&dife, rest) = self
+                .buffer
+                .split_first()
+                .ok_or(DataRecordError::Incomplete)?;
+            self.buffer = rest;
+
+            dib.storage_number |= ((dife & 0x0F) as u64) << (1 + 4 * index);
+            dib.tariff |= (((dife >> 4) & 0x03) as u32) << (2 * index);
+            dib.subunit |= (((dife >> 6) & 0x01) as u16) << index;
+
+            extension = dife & EXTENSION_BIT != 0;
+            index += 1;
+        }
+
+        Ok(dib)
+    }
+
+    /// Read the LVAR preceding variable length data and get the length of the data
+    fn read_lvar(&mut self) -> Result<usize, DataRecordError> {
+        let (&lvar, rest) = self
+            .buffer
+            .split_first()
+            .ok_or(DataRecordError::Incomplete)?;
+        self.buffer = rest;
+
+        match lvar {
+            // Text string
+            0x00..=0xBF => Ok(lvar as usize),
+            // Positive and negative BCD number
+            0xC0..=0xC9 => Ok((lvar - 0xC0) as usize),
+            0xD0..=0xD9 => Ok((lvar - 0xD0) as usize),
+            // Binary number
+            0xE0..=0xEF => Ok((lvar - 0xE0) as usize),
+            0xF0..=0xF4 => Ok(4 * (lvar - 0xEC) as usize),
+            0xF5 => Ok(48),
+            0xF6 => Ok(64),
+            _ => Err(DataRecordError::UnsupportedDataField),
+        }
+    }
+
+    fn read_vib(&mut self) -> Result<ValueInformationBlock, DataRecordError> {
+        let (&vif, rest) = self
+            .buffer
+            .split_first()
+            .ok_or(DataRecordError::Incomplete)?;
+        self.buffer = rest;
+
+        let mut vib = ValueInformationBlock {
+            vif,
+            vife: Vec::new(),
+        };
+
+        let mut extension = vif & EXTENSION_BIT != 0;
+        while extension {
+            let (&vife, rest) = self
+                .buffer
+                .split_first()
+                .ok_or(DataRecordError::Incomplete)?;
+            self.buffer = rest;
+            vib.vife.push(vife).map_err(|_| DataRecordError::Capacity)?;
+            extension = vife & EXTENSION_BIT != 0;
+        }
+
+        Ok(vib)
+    }
+}
+
+impl<'a> Iterator for DataRecords<'a> {
+    type Item = Result<DataRecord<'a>, DataRecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_record() {
+            Ok(record) => record.map(Ok),
+            Err(e) => {
+                // Stop the iteration as the position of the next record is unknown
+                self.buffer = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> DataRecord<'a> {
+    /// Get the raw bytes of the record, i.e. from the DIF up until and including the data
+    pub const fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Get the value of the record according to its data field
+    pub fn value(&self) -> RecordValue<'a> {
+        let data = self.data;
+        match self.dib.data_field {
+            DataField::Integer8
+            | DataField::Integer16
+            | DataField::Integer24
+            | DataField::Integer32
+            | DataField::Integer48
+            | DataField::Integer64 => RecordValue::Integer(decode_integer(data)),
+            DataField::Real32 => RecordValue::Real(f32::from_le_bytes(data.try_into().unwrap())),
+            DataField::Bcd2
+            | DataField::Bcd4
+            | DataField::Bcd6
+            | DataField::Bcd8
+            | DataField::Bcd12 => match decode_bcd(data) {
+                Some(value) => RecordValue::Integer(value),
+                None => RecordValue::Bytes(data),
+            },
+            DataField::NoData | DataField::SelectionForReadout => RecordValue::None,
+            DataField::VariableLength | DataField::SpecialFunction => RecordValue::Bytes(data),
+        }
+    }
+
+    /// Get the decoded value information of the record, if it is in the primary VIF table
+    /// or it is manufacturer specific and decoded by a handler in the [`VifRegistry`] used while parsing
+    pub fn value_information(&self) -> Option<ValueInformation> {
+        decode_vif(self.vib.vif, &self.vib.vife).or(self.manufacturer_info)
+    }
+
+    /// Get the physical quantity of the record, see [`DataRecord::value_information()`] for the unit and the decimal exponent
+    pub fn quantity(&self) -> Option<Quantity> {
+        self.value_information().map(|info| info.quantity)
+    }
+
+    /// Get the value of the record scaled according to its value information, i.e. in the returned unit.
+    /// Time points are not scalar values and are available through [`DataRecord::time_point()`].
+    pub fn scaled_value(&self) -> Option<(f64, Unit)> {
+        let info = self.value_information()?;
+        if info.quantity == Quantity::TimePoint {
+            return None;
+        }
+        let value = match self.value() {
+            RecordValue::Integer(value) => value as f64,
+            RecordValue::Real(value) => value as f64,
+            RecordValue::None | RecordValue::Bytes(_) => return None,
+        };
+        Some((scale(value, info.exponent), info.unit))
+    }
+
+    /// Get the date or date and time of a time point record
+    pub fn time_point(&self) -> Option<DateTime> {
+        match (self.vib.vif & 0x7F, self.data.len()) {
+            (VIF_DATE, 2) => DateTime::from_date(self.data.try_into().unwrap()),
+            (VIF_DATE_TIME, 4) => DateTime::from_date_time(self.data.try_into().unwrap()),
+            (VIF_DATE_TIME, 6) => DateTime::from_date_time_seconds(self.data.try_into().unwrap()),
+            _ => None,
+        }
+    }
+}
+
+impl DataInformationBlock {
+    /// Get whether the record is an instantaneous value for the default tariff and subunit, at any storage number
+    pub const fn is_default_register(&self) -> bool {
+        matches!(self.function, Function::Instantaneous) && self.tariff == 0 && self.subunit == 0
+    }
+
+    /// Get whether the record is the current value of the default register, i.e. for storage number 0.
+    /// Meters commonly report other values with the same VIF, e.g. maximum values, historic values or tariff registers.
+    pub const fn is_current_value(&self) -> bool {
+        self.is_default_register() && self.storage_number == 0
+    }
+}
+
+impl DataField {
+    const fn from_dif(dif: u8) -> Self {
+        match dif & 0x0F {
+            0x00 => DataField::NoData,
+            0x01 => DataField::Integer8,
+            0x02 => DataField::Integer16,
+            0x03 => DataField::Integer24,
+            0x04 => DataField::Integer32,
+            0x05 => DataField::Real32,
+            0x06 => DataField::Integer48,
+            0x07 => DataField::Integer64,
+            0x08 => DataField::SelectionForReadout,
+            0x09 => DataField::Bcd2,
+            0x0A => DataField::Bcd4,
+            0x0B => DataField::Bcd6,
+            0x0C => DataField::Bcd8,
+            0x0D => DataField::VariableLength,
+            0x0E => DataField::Bcd12,
+            _ => DataField::SpecialFunction,
+        }
+    }
+
+    /// Get the data length in bytes, if it is fixed
+    pub const fn data_length(&self) -> Option<usize> {
+        match self {
+            DataField::NoData | DataField::SelectionForReadout => Some(0),
+            DataField::Integer8 | DataField::Bcd2 => Some(1),
+            DataField::Integer16 | DataField::Bcd4 => Some(2),
+            DataField::Integer24 | DataField::Bcd6 => Some(3),
+            DataField::Integer32 | DataField::Real32 | DataField::Bcd8 => Some(4),
+            DataField::Integer48 | DataField::Bcd12 => Some(6),
+            DataField::Integer64 => Some(8),
+            DataField::VariableLength | DataField::SpecialFunction => None,
+        }
+    }
+}
+
+impl Function {
+    const fn from_dif(dif: u8) -> Self {
+        match (dif >> 4) & 0x03 {
+            0 => Function::Instantaneous,
+            1 => Function::Maximum,
+            2 => Function::Minimum,
+            _ => Function::ValueDuringErrorState,
+        }
+    }
+}
+
+/// Decode a VIF like [`decode_vif()`],
+/// but dispatch manufacturer specific VIF's to the handler registered for `manufacturer` in `registry`
+pub fn decode_vif_with_registry(
+    vif: u8,
+    vife: &[u8],
+    manufacturer: Option<ManufacturerCode>,
+    registry: &VifRegistry<'_>,
+) -> Option<ValueInformation> {
+    if vif & 0x7F == MANUFACTURER_SPECIFIC_VIF {
+        return registry.decode(manufacturer?, vife);
+    }
+    decode_vif(vif, vife)
+}
+
+/// Decode a primary VIF and its orthogonal VIFE's
+pub fn decode_vif(vif: u8, vife: &[u8]) -> Option<ValueInformation> {
+    let n = (vif & 0x07) as i8;
+    let nn = (vif & 0x03) as i8;
+    let (quantity, unit, exponent) = match vif & 0x7F {
+        0x00..=0x07 => (Quantity::Energy, Unit::WattHour, n - 3),
+        0x08..=0x0F => (Quantity::Energy, Unit::Joule, n),
+        0x10..=0x17 => (Quantity::Volume, Unit::CubicMeter, n - 6),
+        0x18..=0x1F => (Quantity::Mass, Unit::Kilogram, n - 3),
+        0x20..=0x23 => (Quantity::OnTime, time_unit(vif), 0),
+        0x24..=0x27 => (Quantity::OperatingTime, time_unit(vif), 0),
+        0x28..=0x2F => (Quantity::Power, Unit::Watt, n - 3),
+        0x30..=0x37 => (Quantity::Power, Unit::JoulePerHour, n),
+        0x38..=0x3F => (Quantity::VolumeFlow, Unit::CubicMeterPerHour, n - 6),
+        0x40..=0x47 => (Quantity::VolumeFlow, Unit::CubicMeterPerMinute, n - 7),
+        0x48..=0x4F => (Quantity::VolumeFlow, Unit::CubicMeterPerSecond, n - 9),
+        0x50..=0x57 => (Quantity::MassFlow, Unit::KilogramPerHour, n - 3),
+        0x58..=0x5B => (Quantity::FlowTemperature, Unit::Celsius, nn - 3),
+        0x5C..=0x5F => (Quantity::ReturnTemperature, Unit::Celsius, nn - 3),
+        0x60..=0x63 => (Quantity::TemperatureDifference, Unit::Kelvin, nn - 3),
+        0x64..=0x67 => (Quantity::ExternalTemperature, Unit::Celsius, nn - 3),
+        0x68..=0x6B => (Quantity::Pressure, Unit::Bar, nn - 3),
+        VIF_DATE | VIF_DATE_TIME => (Quantity::TimePoint, Unit::None, 0),
+        0x6E => (Quantity::HcaUnits, Unit::None, 0),
+        0x70..=0x73 => (Quantity::AveragingDuration, time_unit(vif), 0),
+        0x74..=0x77 => (Quantity::ActualityDuration, time_unit(vif), 0),
+        0x78 => (Quantity::FabricationNumber, Unit::None, 0),
+        0x79 => (Quantity::EnhancedIdentification, Unit::None, 0),
+        0x7A => (Quantity::BusAddress, Unit::None, 0),
+        _ => return None,
+    };
+
+    // Apply any orthogonal scaling VIFE's
+    let mut exponent = exponent;
+    for vife in vife {
+        match vife & 0x7F {
+            // Multiplicative correction factor 10^(nnn-6)
+            0x70..=0x77 => exponent += (vife & 0x07) as i8 - 6,
+            // Multiplicative correction factor 10^3
+            0x7D => exponent += 3,
+            _ => {}
+        }
+    }
+
+    Some(ValueInformation {
+        quantity,
+        unit,
+        exponent,
+    })
+}
+
+const fn time_unit(vif: u8) -> Unit {
+    match vif & 0x03 {
+        0 => Unit::Second,
+        1 => Unit::Minute,
+        2 => Unit::Hour,
+        _ => Unit::Day,
+    }
+}
+
+/// Decode a little-endian two's complement integer of up to 8 bytes
+fn decode_integer(data: &[u8]) -> i64 {
+    let mut bytes = [0; 8];
+    bytes[..data.len()].copy_from_slice(data);
+    let shift = 64 - 8 * data.len() as u32;
+    // Sign extend by shifting the value to the top and back
+    (i64::from_le_bytes(bytes) << shift) >> shift
+}
+
+/// Decode a little-endian BCD value where a leading 0xF nibble denotes a negative value
+fn decode_bcd(data: &[u8]) -> Option<i64> {
+    let mut value = 0i64;
+    let mut negative = false;
+    for (index, byte) in data.iter().rev().enumerate() {
+        for (position, digit) in [byte >> 4, byte & 0x0F].into_iter().enumerate() {
+            if index == 0 && position == 0 && digit == 0x0F {
+                negative = true;
+                continue;
+            }
+            if digit > 9 {
+                return None;
+            }
+            value = value * 10 + digit as i64;
+        }
+    }
+
+    Some(if negative { -value } else { value })
+}
+
+fn scale(value: f64, exponent: i8) -> f64 {
+    let mut factor = 1.0;
+    for _ in 0..exponent.unsigned_abs() {
+        factor *= 10.0;
+    }
+
+    if exponent < 0 {
+        value / factor
+    } else {
+        value * factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_filter_quantity() {
+        let buffer = [
+            0x04, 0x06, 0x01, 0x00, 0x00, 0x00, // Energy 1kWh
+            0x04, 0x13, 0x39, 0x30, 0x00, 0x00, // Volume 12.345m3
+            0x02, 0x5B, 0x2A, 0x00, // Flow temperature 42C
+            0x44, 0x13, 0x10, 0x27, 0x00, 0x00, // Volume 10m3 at storage 1
+        ];
+
+        let records: Vec<DataRecord, 4> = DataRecords::new(&buffer)
+            .filter_quantity(Quantity::Volume)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(2, records.len());
+        assert_eq!(Some((12.345, Unit::CubicMeter)), records[0].scaled_value());
+        assert_eq!(0, records[0].dib.storage_number);
+        assert_eq!(Some((10.0, Unit::CubicMeter)), records[1].scaled_value());
+        assert_eq!(1, records[1].dib.storage_number);
+
+        assert_eq!(
+            0,
+            DataRecords::new(&buffer)
+                .filter_quantity(Quantity::Pressure)
+                .count()
+        );
+    }
+
+    #[test]
+    fn can_get_current_value() {
+        let buffer = [
+            0x04, 0x13, 0x39, 0x30, 0x00, 0x00, // Volume 12.345m3
+            0x44, 0x13, 0x10, 0x27, 0x00, 0x00, // Volume 10m3 at storage 1
+            0x14, 0x13, 0x10, 0x27, 0x00, 0x00, // Maximum volume 10m3
+            0x84, 0x10, 0x13, 0x10, 0x27, 0x00, 0x00, // Volume 10m3 at tariff 1
+            0x84, 0x40, 0x13, 0x10, 0x27, 0x00, 0x00, // Volume 10m3 at subunit 1
+        ];
+
+        let records: Vec<DataRecord, 5> = DataRecords::new(&buffer).map(Result::unwrap).collect();
+        let current: Vec<bool, 5> = records.iter().map(|r| r.dib.is_current_value()).collect();
+        let default: Vec<bool, 5> = records
+            .iter()
+            .map(|r| r.dib.is_default_register())
+            .collect();
+        assert_eq!(&[true, false, false, false, false], current.as_slice());
+        assert_eq!(&[true, true, false, false, false], default.as_slice());
+    }
+
+    #[test]
+    fn can_decode_manufacturer_specific_vif() {
+        fn decode_kamstrup(vife: &[u8]) -> Option<ValueInformation> {
+            match vife.first()? & 0x7F {
+                0x01 => Some(ValueInformation {
+                    quantity: Quantity::Power,
+                    unit: Unit::Watt,
+                    exponent: -1,
+                }),
+                _ => None,
+            }
+        }
+        const REGISTRY: VifRegistry = VifRegistry::new(&[(ManufacturerCode::KAM, decode_kamstrup)]);
+        let buffer = [
+            0x04, 0xFF, 0x01, 0xD2, 0x04, 0x00, 0x00, // Power 123.4W
+            0x04, 0xFF, 0x02, 0x01, 0x00, 0x00, 0x00, // Unknown to the handler
+        ];
+
+        let records: Vec<DataRecord, 2> =
+            DataRecords::with_registry(&buffer, ManufacturerCode::KAM, REGISTRY)
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(Some((123.4, Unit::Watt)), records[0].scaled_value());
+        assert_eq!(None, records[1].value_information());
+
+        let record = DataRecords::with_registry(&buffer, ManufacturerCode::DME, REGISTRY)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(None, record.value_information());
+
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+        assert_eq!(None, record.value_information());
+    }
+
+    #[test]
+    fn can_read_records() {
+        let buffer = [
+            0x2F, 0x2F, // Idle fillers
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume 42l
+            0x42, 0x6C, 0x1F, 0x31, // Date at storage number 1
+            0x84, 0x10, 0x06, 0x01, 0x00, 0x00, 0x00, // Energy 1kWh at tariff 1
+            0x0F, 0x01, 0x02, // Manufacturer specific data
+        ];
+        let mut records = DataRecords::new(&buffer);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(DataField::Integer32, record.dib.data_field);
+        assert_eq!(0, record.dib.storage_number);
+        assert_eq!(RecordValue::Integer(42), record.value());
+        assert_eq!(Some((0.042, Unit::CubicMeter)), record.scaled_value());
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(DataField::Integer16, record.dib.data_field);
+        assert_eq!(1, record.dib.storage_number);
+        assert_eq!(&buffer[8..12], record.raw());
+        assert_eq!(
+            Quantity::TimePoint,
+            record.value_information().unwrap().quantity
+        );
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(1, record.dib.tariff);
+        assert_eq!(Some((1000.0, Unit::WattHour)), record.scaled_value());
+
+        assert!(records.next().is_none());
+        assert_eq!(
+            Some([0x01, 0x02].as_slice()),
+            records.manufacturer_specific()
+        );
+    }
+
+    #[test]
+    fn can_parse_manufacturer_specific_records() {
+        struct Handler;
+        impl ManufacturerHandler for Handler {
+            fn records<'a>(
+                &self,
+                manufacturer: ManufacturerCode,
+                data: &'a [u8],
+            ) -> Option<&'a [u8]> {
+                // A one byte manufacturer header followed by standard data records
+                (manufacturer == ManufacturerCode::KAM && data.first() == Some(&0x01))
+                    .then(|| &data[1..])
+            }
+        }
+        let buffer = [
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume 42l
+            0x0F, 0x01, // Manufacturer specific data with header
+            0x02, 0x5A, 0x10, 0x00, // Flow temperature 1.6C
+        ];
+
+        let mut records =
+            DataRecords::new(&buffer).with_manufacturer_handler(ManufacturerCode::KAM, &Handler);
+        records.next().unwrap().unwrap();
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(Some(Quantity::FlowTemperature), record.quantity());
+        assert_eq!(&buffer[8..], record.raw());
+        assert!(records.next().is_none());
+        assert_eq!(Some(&buffer[7..]), records.manufacturer_specific());
+
+        // Unknown manufacturer falls back to the raw bytes
+        let mut records =
+            DataRecords::new(&buffer).with_manufacturer_handler(ManufacturerCode::DME, &Handler);
+        records.next().unwrap().unwrap();
+        assert!(records.next().is_none());
+        assert_eq!(Some(&buffer[7..]), records.manufacturer_specific());
+    }
+
+    #[test]
+    fn can_read_extended_status() {
+        #[rustfmt::skip]
+        let buffer = [
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume
+            0x3F, 0x01, 0x80, 0x00, // Extended status block
+        ];
+
+        let mut records = DataRecords::new(&buffer);
+        assert_eq!(
+            Some((0.042, Unit::CubicMeter)),
+            records.next().unwrap().unwrap().scaled_value()
+        );
+        assert!(records.next().is_none());
+        assert_eq!(
+            Some([0x01, 0x80, 0x00].as_slice()),
+            records.extended_status()
+        );
+        assert_eq!(None, records.manufacturer_specific());
+    }
+
+    #[test]
+    fn can_read_operating_time() {
+        let buffer = [0x03, 0x26, 0x39, 0x30, 0x00]; // Operating time 12345h
+
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+
+        assert_eq!(
+            Quantity::OperatingTime,
+            record.value_information().unwrap().quantity
+        );
+        assert_eq!(Some((12345.0, Unit::Hour)), record.scaled_value());
+        assert_eq!(None, record.time_point());
+    }
+
+    #[test]
+    fn can_read_time_points() {
+        let buffer = [
+            0x02, 0x6C, 0x1F, 0x31, // Date
+            0x04, 0x6D, 0x1E, 0x0B, 0x0F, 0x3C, // Date and time
+        ];
+        let mut records = DataRecords::new(&buffer);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(None, record.scaled_value());
+        let date = record.time_point().unwrap();
+        assert_eq!((2024, 1, 31), (date.year, date.month, date.day));
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(None, record.scaled_value());
+        let date_time = record.time_point().unwrap();
+        assert_eq!(
+            (2024, 12, 15, 11, 30),
+            (
+                date_time.year,
+                date_time.month,
+                date_time.day,
+                date_time.hour,
+                date_time.minute
+            )
+        );
+    }
+
+    #[test]
+    fn can_parse_records() {
+        let buffer = [
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume
+            0x42, 0x6C, 0x1F, 0x31, // Date
+            0x04, 0x06, 0x01, 0x00, 0x00, 0x00, // Energy
+            0x02, 0x59, 0x2B, 0x17, // Flow temperature
+        ];
+
+        let records = parse_records::<8>(&buffer).unwrap();
+        assert_eq!(4, records.len());
+        assert_eq!(0x6C, records[1].vib.vif);
+        assert_eq!(0x59, records[3].vib.vif);
+        assert_eq!(&buffer[6..10], records[1].raw());
+
+        assert_eq!(Err(ParseError::TooManyRecords), parse_records::<3>(&buffer));
+        assert_eq!(
+            Err(ParseError::Record(DataRecordError::Incomplete)),
+            parse_records::<8>(&buffer[..buffer.len() - 1])
+        );
+    }
+
+    #[test]
+    fn can_read_variable_length_records() {
+        #[rustfmt::skip]
+        let buffer = [
+            0x0D, 0x78, 0x03, 0x43, 0x42, 0x41, // Fabrication number as text
+            0x8D, 0x00, 0xFD, 0x3A, 0xE2, 0x34, 0x12, // Binary at storage number 0 with a DIFE
+            0x2F, 0x2F, // Idle fillers
+        ];
+        let mut records = DataRecords::new(&buffer);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(DataField::VariableLength, record.dib.data_field);
+        assert_eq!(RecordValue::Bytes(&[0x43, 0x42, 0x41]), record.value());
+        assert_eq!(&buffer[..6], record.raw());
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(0xFD, record.vib.vif);
+        assert_eq!(&[0x3A], record.vib.vife.as_slice());
+        assert_eq!(&[0x34, 0x12], record.data);
+
+        assert!(records.next().is_none());
+
+        // Truncated data and reserved LVAR
+        let mut records = DataRecords::new(&buffer[..5]);
+        assert_eq!(Some(Err(DataRecordError::Incomplete)), records.next());
+        let mut records = DataRecords::new(&[0x0D, 0x78, 0xFB]);
+        assert_eq!(
+            Some(Err(DataRecordError::UnsupportedDataField)),
+            records.next()
+        );
+    }
+
+    #[test]
+    fn can_decode_kamstrup_heat_values() {
+        // The decrypted payload of a Kamstrup Multical heat meter
+        #[rustfmt::skip]
+        let buffer = [
+            0x2F, 0x2F,
+            0x04, 0x06, 0xA4, 0x1A, 0x00, 0x00, // Energy 6820kWh
+            0x04, 0x14, 0x49, 0xAD, 0x00, 0x00, // Volume 443.61m3
+            0x02, 0x59, 0x2B, 0x17, // Flow temperature 59.31C
+        ];
+        let mut records = DataRecords::new(&buffer);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(6820), record.value());
+        assert_eq!(Some(Quantity::Energy), record.quantity());
+        assert_eq!(3, record.value_information().unwrap().exponent);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(44361), record.value());
+        assert_eq!(Some(Quantity::Volume), record.quantity());
+        assert_eq!(-2, record.value_information().unwrap().exponent);
+
+        let record = records.next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(5931), record.value());
+        assert_eq!(Some(Quantity::FlowTemperature), record.quantity());
+
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn can_read_truncated_record() {
+        let buffer = [0x04, 0x13, 0x2A, 0x00];
+        let mut records = DataRecords::new(&buffer);
+        assert_eq!(Some(Err(DataRecordError::Incomplete)), records.next());
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn can_decode_negative_power() {
+        // -1500W
+        let buffer = [0x04, 0x2B, 0x24, 0xFA, 0xFF, 0xFF];
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(-1500), record.value());
+        assert_eq!(Some((-1500.0, Unit::Watt)), record.scaled_value());
+
+        // -1234.5W with a 10^-1 exponent
+        let buffer = [0x02, 0x2A, 0xC7, 0xCF];
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(-12345), record.value());
+        assert_eq!(Some((-1234.5, Unit::Watt)), record.scaled_value());
+
+        // -123W as BCD
+        let buffer = [0x0A, 0x2B, 0x23, 0xF1];
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+        assert_eq!(RecordValue::Integer(-123), record.value());
+    }
+
+    #[test]
+    fn can_decode_data_field_widths() {
+        #[rustfmt::skip]
+        let buffer = [
+            0x01, 0x13, 0xFE, // Integer8
+            0x03, 0x13, 0x01, 0x02, 0x03, // Integer24
+            0x06, 0x13, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, // Integer48
+            0x07, 0x13, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // Integer64
+            0x09, 0x13, 0x42, // Bcd2
+            0x0B, 0x13, 0x56, 0x34, 0x12, // Bcd6
+            0x0C, 0x13, 0x78, 0x56, 0x34, 0x12, // Bcd8
+            0x0E, 0x13, 0x12, 0x90, 0x78, 0x56, 0x34, 0x12, // Bcd12
+        ];
+
+        let values: Vec<RecordValue, 8> = DataRecords::new(&buffer)
+            .map(|record| record.unwrap().value())
+            .collect();
+        assert_eq!(
+            [
+                RecordValue::Integer(-2),
+                RecordValue::Integer(0x030201),
+                RecordValue::Integer(0x0100_0000_0001),
+                RecordValue::Integer(-1),
+                RecordValue::Integer(42),
+                RecordValue::Integer(123456),
+                RecordValue::Integer(12345678),
+                RecordValue::Integer(123456789012),
+            ],
+            values.as_slice()
+        );
+    }
+
+    #[test]
+    fn can_decode_real_energy() {
+        // 12.5kWh
+        let mut buffer = [0x05, 0x06, 0x00, 0x00, 0x00, 0x00];
+        buffer[2..].copy_from_slice(&12.5f32.to_le_bytes());
+        let record = DataRecords::new(&buffer).next().unwrap().unwrap();
+        assert_eq!(RecordValue::Real(12.5), record.value());
+        assert_eq!(Some((12500.0, Unit::WattHour)), record.scaled_value());
+    }
+}
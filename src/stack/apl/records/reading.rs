@@ -0,0 +1,84 @@
+use super::{DataRecords, Quantity, Unit};
+
+/// The standard quantities of a typical heat or water meter
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeterReading {
+    /// The accumulated energy
+    pub energy: Option<(f64, Unit)>,
+    /// The accumulated volume
+    pub volume: Option<(f64, Unit)>,
+    /// The flow temperature
+    pub flow_temperature: Option<(f64, Unit)>,
+    /// The return temperature
+    pub return_temperature: Option<(f64, Unit)>,
+    /// The volume flow rate
+    pub volume_flow: Option<(f64, Unit)>,
+}
+
+impl MeterReading {
+    /// Pick out the standard quantities from the data records.
+    /// Only current values are considered, see [`DataInformationBlock::is_current_value()`],
+    /// and any other records are ignored. Decoding stops at the first malformed record.
+    pub fn from_records(records: &DataRecords<'_>) -> Self {
+        let mut reading = Self::default();
+
+        for record in records.clone().map_while(Result::ok) {
+            if !record.dib.is_current_value() {
+                continue;
+            }
+
+            let (Some(info), Some(value)) = (record.value_information(), record.scaled_value())
+            else {
+                continue;
+            };
+            let field = match info.quantity {
+                Quantity::Energy => &mut reading.energy,
+                Quantity::Volume => &mut reading.volume,
+                Quantity::FlowTemperature => &mut reading.flow_temperature,
+                Quantity::ReturnTemperature => &mut reading.return_temperature,
+                Quantity::VolumeFlow => &mut reading.volume_flow,
+                _ => continue,
+            };
+            field.get_or_insert(value);
+        }
+
+        reading
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_heat_meter() {
+        // Given
+        #[rustfmt::skip]
+        let payload = [
+            0x04, 0x6D, 0x1E, 0x0B, 0x8F, 0x2C, // Date and time
+            0x04, 0x06, 0xA4, 0x1A, 0x00, 0x00, // Energy 6820kWh
+            0x04, 0x13, 0x4A, 0xC4, 0x06, 0x00, // Volume 443.466m3
+            0x02, 0x5A, 0x51, 0x02, // Flow temperature 59.3C
+            0x02, 0x5E, 0x7D, 0x01, // Return temperature 38.1C
+            0x04, 0x3B, 0x4C, 0x05, 0x00, 0x00, // Volume flow 1.356m3/h
+            0x04, 0x2B, 0xD2, 0x04, 0x00, 0x00, // Power 1234W
+            0x44, 0x06, 0x10, 0x1A, 0x00, 0x00, // Energy 6672kWh at storage 1
+        ];
+
+        // When
+        let reading = MeterReading::from_records(&DataRecords::new(&payload));
+
+        // Then
+        assert_eq!(Some((6820000.0, Unit::WattHour)), reading.energy);
+        assert_eq!(Some((443.466, Unit::CubicMeter)), reading.volume);
+        assert_eq!(Some((59.3, Unit::Celsius)), reading.flow_temperature);
+        assert_eq!(Some((38.1, Unit::Celsius)), reading.return_temperature);
+        assert_eq!(Some((1.356, Unit::CubicMeterPerHour)), reading.volume_flow);
+    }
+
+    #[test]
+    fn can_read_empty() {
+        let reading = MeterReading::from_records(&DataRecords::new(&[]));
+        assert_eq!(MeterReading::default(), reading);
+    }
+}
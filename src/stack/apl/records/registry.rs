@@ -0,0 +1,36 @@
+use crate::ManufacturerCode;
+
+use super::ValueInformation;
+
+/// Handler decoding the VIFE's following a manufacturer specific VIF
+pub type VifHandler = fn(vife: &[u8]) -> Option<ValueInformation>;
+
+/// Handler for the manufacturer specific data following the 0x0F or 0x1F DIF.
+/// Some manufacturers and OMS profiles use a standardized structure, where standard data records follow a manufacturer header.
+pub trait ManufacturerHandler {
+    /// Get the part of the manufacturer specific `data` sent by `manufacturer` that contains standard data records,
+    /// or `None` if the data is not understood and should be kept as raw bytes
+    fn records<'a>(&self, manufacturer: ManufacturerCode, data: &'a [u8]) -> Option<&'a [u8]>;
+}
+
+/// Registry of handlers for manufacturer specific VIF's, keyed by manufacturer code
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VifRegistry<'a> {
+    handlers: &'a [(ManufacturerCode, VifHandler)],
+}
+
+impl<'a> VifRegistry<'a> {
+    /// Create a new registry with the handlers for each manufacturer
+    pub const fn new(handlers: &'a [(ManufacturerCode, VifHandler)]) -> Self {
+        Self { handlers }
+    }
+
+    /// Decode the VIFE's following a manufacturer specific VIF using the handler registered for the manufacturer
+    pub fn decode(&self, manufacturer: ManufacturerCode, vife: &[u8]) -> Option<ValueInformation> {
+        let (_, handler) = self
+            .handlers
+            .iter()
+            .find(|(code, _)| *code == manufacturer)?;
+        handler(vife)
+    }
+}
@@ -0,0 +1,134 @@
+/// A date and time point as encoded in a data record.
+/// The time fields are zero for a date only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Decode a data type G date, i.e. the 16 bit date
+    pub const fn from_date(data: [u8; 2]) -> Option<Self> {
+        Self::new(
+            year(data[0], data[1]),
+            data[1] & 0x0F,
+            data[0] & 0x1F,
+            0,
+            0,
+            0,
+        )
+    }
+
+    /// Decode a data type F date and time, i.e. the 32 bit date and time with minute resolution
+    pub const fn from_date_time(data: [u8; 4]) -> Option<Self> {
+        // The time invalid bit
+        if data[0] & 0x80 != 0 {
+            return None;
+        }
+
+        Self::new(
+            year(data[2], data[3]),
+            data[3] & 0x0F,
+            data[2] & 0x1F,
+            data[1] & 0x1F,
+            data[0] & 0x3F,
+            0,
+        )
+    }
+
+    /// Decode a data type I date and time, i.e. the 48 bit date and time with second resolution
+    pub const fn from_date_time_seconds(data: [u8; 6]) -> Option<Self> {
+        // The time invalid bit
+        if data[1] & 0x80 != 0 {
+            return None;
+        }
+
+        Self::new(
+            year(data[3], data[4]),
+            data[4] & 0x0F,
+            data[3] & 0x1F,
+            data[2] & 0x1F,
+            data[1] & 0x3F,
+            data[0] & 0x3F,
+        )
+    }
+
+    const fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        if month < 1 || month > 12 || day < 1 || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+
+        Some(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+/// Get the year from the day and month bytes, where the three low bits are in the day byte and the four high bits are in the month byte
+const fn year(day: u8, month: u8) -> u16 {
+    2000 + (((day & 0xE0) >> 5) | ((month & 0xF0) >> 1)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_date() {
+        assert_eq!(
+            Some(DateTime {
+                year: 2024,
+                month: 1,
+                day: 31,
+                hour: 0,
+                minute: 0,
+                second: 0,
+            }),
+            DateTime::from_date([0x1F, 0x31])
+        );
+        assert_eq!(None, DateTime::from_date([0x00, 0x00]));
+    }
+
+    #[test]
+    fn can_decode_date_time() {
+        assert_eq!(
+            Some(DateTime {
+                year: 2024,
+                month: 12,
+                day: 15,
+                hour: 11,
+                minute: 30,
+                second: 0,
+            }),
+            DateTime::from_date_time([0x1E, 0x0B, 0x0F, 0x3C])
+        );
+
+        // Invalid time
+        assert_eq!(None, DateTime::from_date_time([0x9E, 0x0B, 0x0F, 0x3C]));
+    }
+
+    #[test]
+    fn can_decode_date_time_seconds() {
+        assert_eq!(
+            Some(DateTime {
+                year: 2024,
+                month: 12,
+                day: 15,
+                hour: 11,
+                minute: 30,
+                second: 45,
+            }),
+            DateTime::from_date_time_seconds([0x2D, 0x1E, 0x0B, 0x0F, 0x3C, 0x00])
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use core::ops::Range;
+
+use super::{apl, Layer, Mode, ReadError, Stack};
+
+/// The structure of a frame as a tree of layers and their fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct DissectionTree {
+    /// The layer nodes in frame order
+    pub nodes: Vec<DissectionNode>,
+}
+
+/// A layer or field in a [`DissectionTree`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DissectionNode {
+    pub name: String,
+    /// The byte range in the frame data after the block CRC's are removed, see [`super::LayerMap`]
+    pub range: Range<usize>,
+    /// The decoded value, if any
+    pub value: Option<String>,
+    pub children: Vec<DissectionNode>,
+}
+
+impl DissectionNode {
+    fn new(name: &str, range: Range<usize>, value: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            range,
+            value,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<A: Layer> Stack<A> {
+    /// Read a packet from a byte buffer and describe the fields of each layer as a tree, e.g. for a hex viewer
+    pub fn dissect(&self, buffer: &[u8], mode: Mode) -> Result<DissectionTree, ReadError> {
+        let (packet, map) = self.read_with_map(buffer, mode)?;
+        let mut nodes = Vec::new();
+
+        if let Some(dll) = &packet.dll {
+            let start = map.dll.start;
+            let mut node = DissectionNode::new("DLL", map.dll.clone(), None);
+            node.children = vec![
+                DissectionNode::new("L field", start..start + 1, None),
+                DissectionNode::new(
+                    "C field",
+                    start + 1..start + 2,
+                    Some(format!("{:#04x}", dll.control)),
+                ),
+                DissectionNode::new(
+                    "Address",
+                    start + 2..map.dll.end,
+                    Some(format!("{}", dll.address)),
+                ),
+            ];
+            nodes.push(node);
+        }
+
+        if let (Some(ell), Some(range)) = (&packet.ell, map.ell.clone()) {
+            let start = range.start;
+            let mut node = DissectionNode::new("ELL", range, None);
+            node.children = vec![
+                DissectionNode::new("CI", start..start + 1, Some(format!("{:#04x}", ell.ci()))),
+                DissectionNode::new("CC", start + 1..start + 2, Some(format!("{:?}", ell.cc()))),
+                DissectionNode::new("ACC", start + 2..start + 3, Some(format!("{}", ell.acc()))),
+            ];
+            let mut offset = start + 3;
+            if let Some(dest) = ell.dest() {
+                node.children.push(DissectionNode::new(
+                    "Destination",
+                    offset..offset + 8,
+                    Some(format!("{}", dest)),
+                ));
+                offset += 8;
+            }
+            if let Some(sn) = ell.sn() {
+                let mut sn_node =
+                    DissectionNode::new("SN", offset..offset + 4, Some(format!("{:#010x}", sn)));
+                // The session, time and encryption fields are bit fields of the little endian session number
+                sn_node.children = vec![
+                    DissectionNode::new(
+                        "Session",
+                        offset..offset + 1,
+                        Some(format!("{}", sn & 0x0F)),
+                    ),
+                    DissectionNode::new(
+                        "Time",
+                        offset..offset + 4,
+                        Some(format!("{}", (sn >> 4) & 0x01FF_FFFF)),
+                    ),
+                    DissectionNode::new(
+                        "Encryption",
+                        offset + 3..offset + 4,
+                        Some(format!("{}", sn >> 29)),
+                    ),
+                ];
+                node.children.push(sn_node);
+                node.children.push(DissectionNode::new(
+                    "Payload CRC",
+                    offset + 4..offset + 6,
+                    ell.payload_crc().map(|crc| format!("{:#06x}", crc)),
+                ));
+            }
+            nodes.push(node);
+        }
+
+        if let (Some(tpl), Some(range)) = (&packet.tpl, map.tpl.clone()) {
+            let mut offset = range.start + 1;
+            let mut node = DissectionNode::new("TPL", range.clone(), None);
+            node.children.push(DissectionNode::new(
+                "CI",
+                range.start..offset,
+                Some(format!("{:#04x}", tpl.ci())),
+            ));
+            if let Some(address) = tpl.address() {
+                node.children.push(DissectionNode::new(
+                    "Address",
+                    offset..offset + 8,
+                    Some(format!("{}", address)),
+                ));
+                offset += 8;
+            }
+            if let (Some(acc), Some(sts), Some(cw)) = (tpl.acc(), tpl.sts(), tpl.cw()) {
+                node.children.extend([
+                    DissectionNode::new("ACC", offset..offset + 1, Some(format!("{}", acc))),
+                    DissectionNode::new(
+                        "STS",
+                        offset + 1..offset + 2,
+                        Some(format!("{:#04x}", sts)),
+                    ),
+                    DissectionNode::new("CW", offset + 2..range.end, Some(format!("{:#06x}", cw))),
+                ]);
+            }
+            nodes.push(node);
+        }
+
+        let mut node = DissectionNode::new("APL", map.apl.clone(), None);
+        let mut offset = map.apl.start;
+        for (ci, data) in apl::CiChain::new(&packet.apl) {
+            let end = offset + 1 + data.len();
+            node.children.push(DissectionNode::new(
+                "CI",
+                offset..end,
+                Some(format!("{:#04x}", ci)),
+            ));
+            offset = end;
+        }
+        nodes.push(node);
+
+        Ok(DissectionTree { nodes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::phl;
+
+    #[test]
+    fn can_dissect() {
+        let stack = Stack::default();
+        #[rustfmt::skip]
+        let frame = &[
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+
+        let tree = stack.dissect(frame, Mode::ModeCFFB).unwrap();
+
+        let names: Vec<&str> = tree.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(vec!["DLL", "APL"], names);
+
+        let dll = &tree.nodes[0];
+        assert_eq!(0..10, dll.range);
+        assert_eq!("C field", dll.children[1].name);
+        assert_eq!(Some("0x44".into()), dll.children[1].value);
+        assert_eq!(2..10, dll.children[2].range);
+
+        let apl = &tree.nodes[1];
+        assert_eq!(10..18, apl.range);
+        assert_eq!(1, apl.children.len());
+        assert_eq!(Some("0xa0".into()), apl.children[0].value);
+    }
+
+    #[test]
+    fn can_dissect_long_ell() {
+        // Given
+        let stack = Stack::default();
+        #[rustfmt::skip]
+        let data = [
+            0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0x8D, 0x20, 0x01, 0x23, 0x01, 0x00, 0x00, 0x34, 0x12,
+            0xA0, 0x00, 0x01,
+        ];
+        let mut frame = Vec::new();
+        phl::write_ffa_frame(&mut frame, &data).unwrap();
+
+        // When
+        let tree = stack.dissect(&frame, Mode::ModeCFFA).unwrap();
+
+        // Then
+        let ell = &tree.nodes[1];
+        assert_eq!("ELL", ell.name);
+        assert_eq!(10..19, ell.range);
+        let names: Vec<&str> = ell.children.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(vec!["CI", "CC", "ACC", "SN", "Payload CRC"], names);
+
+        let sn = &ell.children[3];
+        assert_eq!(13..17, sn.range);
+        assert_eq!(Some("0x00000123".into()), sn.value);
+        assert_eq!(
+            vec![
+                ("Session", 13..14, Some("3".into())),
+                ("Time", 13..17, Some("18".into())),
+                ("Encryption", 16..17, Some("0".into())),
+            ],
+            sn.children
+                .iter()
+                .map(|node| (node.name.as_str(), node.range.clone(), node.value.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        let payload_crc = &ell.children[4];
+        assert_eq!(17..19, payload_crc.range);
+        assert_eq!(Some("0x1234".into()), payload_crc.value);
+    }
+}
@@ -1,12 +1,20 @@
-use bytes::{BufMut, BytesMut};
+use bytes::BufMut;
 
 use crate::address::WMBusAddress;
 
 use super::{Layer, Packet, ReadError, WriteError};
 
-const HEADER_LENGTH: usize = 10;
+pub(crate) const HEADER_LENGTH: usize = 10;
 
-/// Data-Link Layer
+/// The C field of an acknowledgement (ACK)
+pub const C_ACK: u8 = 0x00;
+/// The C field of a confirmation of an installation request (CNF-IR)
+pub const C_CNF_IR: u8 = 0x06;
+/// The C field bits that are not part of the function code, i.e. the ACD and DFC bits of a response
+const C_RESPONSE_FLAGS: u8 = 0x30;
+
+/// Data-Link Layer.
+/// The header is the L, C, M and A fields in all modes, i.e. the address is always the 2 byte manufacturer and the 6 byte A field.
 pub struct Dll<A: Layer> {
     above: A,
 }
@@ -15,6 +23,8 @@ pub struct Dll<A: Layer> {
 pub struct DllFields {
     pub control: u8,
     pub address: WMBusAddress,
+    /// The address bytes as they appear in the frame, e.g. for comparing against a known address without BCD conversion
+    pub raw_address: [u8; 8],
 }
 
 #[derive(Debug, PartialEq)]
@@ -33,6 +43,21 @@ impl From<Error> for ReadError {
     }
 }
 
+impl DllFields {
+    pub fn new(control: u8, address: WMBusAddress) -> Self {
+        Self {
+            control,
+            raw_address: address.get_bytes(),
+            address,
+        }
+    }
+
+    /// Get whether the C field is an acknowledgement or confirmation without application data, i.e. ACK or CNF-IR
+    pub const fn is_acknowledgement(&self) -> bool {
+        matches!(self.control & !C_RESPONSE_FLAGS, C_ACK | C_CNF_IR)
+    }
+}
+
 impl<A: Layer> Dll<A> {
     pub const fn new(above: A) -> Self {
         Self { above }
@@ -45,10 +70,11 @@ impl<A: Layer> Layer for Dll<A> {
             return Err(Error::Incomplete)?;
         }
 
+        let raw_address: [u8; 8] = buffer[2..10].try_into().unwrap();
         packet.dll = Some(DllFields {
             control: buffer[1],
-            address: WMBusAddress::from_bytes(buffer[2..10].try_into().unwrap())
-                .map_err(|_| Error::BcdConversion)?,
+            address: WMBusAddress::from_bytes(raw_address).map_err(|_| Error::BcdConversion)?,
+            raw_address,
         });
 
         self.above.read(packet, &buffer[HEADER_LENGTH..])
@@ -56,10 +82,14 @@ impl<A: Layer> Layer for Dll<A> {
 
     fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError> {
         let fields = packet.dll.as_ref().unwrap();
+        // The L field is written by the physical layer
+        if writer.remaining_mut() < HEADER_LENGTH - 1 {
+            return Err(WriteError::BufferTooSmall);
+        }
         writer.put_u8(fields.control);
         writer.put_slice(&fields.address.get_bytes());
         self.above.write(writer, packet)?;
@@ -94,6 +124,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "strict-layout"))]
     fn can_read_hyd_reversed() {
         // Given
         let mut packet: Packet = Packet::new(Mode::ModeTMTO);
@@ -109,4 +140,27 @@ mod tests {
             packet.dll.unwrap().address
         );
     }
+
+    #[test]
+    fn can_read_raw_address() {
+        // Given
+        let mut packet: Packet = Packet::new(Mode::ModeTMTO);
+        let dll = Dll::new(Apl::new());
+        let buffer: [u8; 10] = [0x00, 0x00, 0x24, 0x23, 0x85, 0x07, 0x47, 0x35, 0x04, 0x09];
+
+        // When
+        dll.read(&mut packet, &buffer).unwrap();
+
+        // Then
+        let fields = packet.dll.unwrap();
+        assert_eq!(buffer[2..10], fields.raw_address);
+        assert_eq!(
+            [0x24, 0x23, 0x14, 0x89, 0x81, 0x44, 0x20, 0x04],
+            DllFields::new(
+                0x44,
+                WMBusAddress::new(ManufacturerCode::HYD, 44818914, 0x20, DeviceType::Heat)
+            )
+            .raw_address
+        );
+    }
 }
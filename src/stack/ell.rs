@@ -1,6 +1,6 @@
-use bytes::BytesMut;
+use bytes::BufMut;
 
-use super::{Layer, Packet, ReadError, WriteError};
+use super::{apl, phl, Layer, Packet, ReadError, WriteError};
 use crate::address::WMBusAddress;
 
 /// Extended Link Layer
@@ -39,6 +39,10 @@ pub enum EllFields {
 pub enum Error {
     Incomplete,
     BcdConversion,
+    /// The packet has no extended link layer with an encrypted payload
+    NotEncrypted,
+    /// The payload CRC does not match the decrypted payload
+    PayloadCrc,
 }
 
 impl From<Error> for ReadError {
@@ -56,6 +60,56 @@ impl<A: Layer> Ell<A> {
     }
 }
 
+/// Communication control (CC) field
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommunicationControl(pub u8);
+
+impl CommunicationControl {
+    pub const BIDIRECTIONAL: u8 = 0x80;
+    pub const RESPONSE_DELAY: u8 = 0x40;
+    pub const SYNCHRONIZED: u8 = 0x20;
+    pub const HOP_COUNT: u8 = 0x10;
+    pub const PRIORITY: u8 = 0x08;
+    pub const ACCESSIBILITY: u8 = 0x04;
+    pub const REPEATED_ACCESS: u8 = 0x02;
+
+    /// The meter supports bidirectional communication (B)
+    pub const fn bidirectional(&self) -> bool {
+        self.0 & Self::BIDIRECTIONAL != 0
+    }
+
+    /// The meter responds with a fast response delay (D)
+    pub const fn response_delay(&self) -> bool {
+        self.0 & Self::RESPONSE_DELAY != 0
+    }
+
+    /// The frame is transmitted synchronously (S)
+    pub const fn synchronized(&self) -> bool {
+        self.0 & Self::SYNCHRONIZED != 0
+    }
+
+    /// The frame was forwarded by a repeater (H)
+    pub const fn hop_count(&self) -> bool {
+        self.0 & Self::HOP_COUNT != 0
+    }
+
+    /// The frame has high priority (P)
+    pub const fn priority(&self) -> bool {
+        self.0 & Self::PRIORITY != 0
+    }
+
+    /// The meter is accessible after the transmission (A)
+    pub const fn accessibility(&self) -> bool {
+        self.0 & Self::ACCESSIBILITY != 0
+    }
+
+    /// The frame is a repeated access (R)
+    pub const fn repeated_access(&self) -> bool {
+        self.0 & Self::REPEATED_ACCESS != 0
+    }
+}
+
 impl EllFields {
     pub const fn ci(&self) -> u8 {
         match self {
@@ -65,12 +119,136 @@ impl EllFields {
             EllFields::LongDest { .. } => 0x8F,
         }
     }
+
+    /// Get the communication control field
+    pub const fn cc(&self) -> CommunicationControl {
+        match self {
+            EllFields::Short { cc, .. }
+            | EllFields::Long { cc, .. }
+            | EllFields::ShortDest { cc, .. }
+            | EllFields::LongDest { cc, .. } => CommunicationControl(*cc),
+        }
+    }
+
+    /// Get the access number
+    pub const fn acc(&self) -> u8 {
+        match self {
+            EllFields::Short { acc, .. }
+            | EllFields::Long { acc, .. }
+            | EllFields::ShortDest { acc, .. }
+            | EllFields::LongDest { acc, .. } => *acc,
+        }
+    }
+
+    /// Get the session number, if any
+    pub const fn sn(&self) -> Option<u32> {
+        match self {
+            EllFields::Long { sn, .. } | EllFields::LongDest { sn, .. } => Some(*sn),
+            EllFields::Short { .. } | EllFields::ShortDest { .. } => None,
+        }
+    }
+
+    /// Get the payload CRC, if any. It is encrypted until the payload is decrypted.
+    pub const fn payload_crc(&self) -> Option<u16> {
+        match self {
+            EllFields::Long { payload_crc, .. } | EllFields::LongDest { payload_crc, .. } => {
+                *payload_crc
+            }
+            EllFields::Short { .. } | EllFields::ShortDest { .. } => None,
+        }
+    }
+
+    /// Get whether the payload is AES-128-CTR encrypted, i.e. the encryption field of the session number is 1
+    pub const fn is_encrypted(&self) -> bool {
+        matches!(self.sn(), Some(sn) if sn >> 29 == 1)
+    }
+
+    /// Get the destination address, if any
+    pub const fn dest(&self) -> Option<&WMBusAddress> {
+        match self {
+            EllFields::ShortDest { dest, .. } | EllFields::LongDest { dest, .. } => Some(dest),
+            EllFields::Short { .. } | EllFields::Long { .. } => None,
+        }
+    }
+
+    /// Get whether a repeater with address `my_address` should forward the frame.
+    /// A frame is only forwarded once, i.e. if neither the hop count nor the repeated access bit is already set,
+    /// and never if this repeater is the destination of the frame.
+    pub fn should_repeat(&self, my_address: &WMBusAddress) -> bool {
+        let cc = self.cc();
+        if cc.hop_count() || cc.repeated_access() {
+            return false;
+        }
+
+        self.dest() != Some(my_address)
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl EllFields {
+    /// Decrypt the payload CRC and the application layer `apl` in place with AES-128-CTR.
+    /// The counter block is derived from the data link layer `address`, the communication control field and the session number.
+    pub(crate) fn decrypt(
+        &mut self,
+        address: &[u8; 8],
+        key: &[u8; 16],
+        apl: &mut [u8],
+    ) -> Result<(), Error> {
+        use aes::cipher::{KeyIvInit, StreamCipher};
+
+        let (cc, sn, payload_crc) = match self {
+            EllFields::Long {
+                cc,
+                sn,
+                payload_crc: Some(payload_crc),
+                ..
+            }
+            | EllFields::LongDest {
+                cc,
+                sn,
+                payload_crc: Some(payload_crc),
+                ..
+            } if *sn >> 29 == 1 => (*cc, *sn, payload_crc),
+            _ => return Err(Error::NotEncrypted),
+        };
+
+        // M, A, CC, SN, FN and BC where the frame number and block counter start at 0
+        let mut iv = [0; 16];
+        iv[..8].copy_from_slice(address);
+        iv[8] = cc;
+        iv[9..13].copy_from_slice(&sn.to_le_bytes());
+
+        // The payload CRC is the first encrypted field
+        let mut cipher = ctr::Ctr128BE::<aes::Aes128>::new(key.into(), &iv.into());
+        let mut crc = payload_crc.to_le_bytes();
+        cipher.apply_keystream(&mut crc);
+        let crc = u16::from_le_bytes(crc);
+
+        // Verify the payload CRC on a copy, so that the payload is left encrypted if the key is wrong
+        let mut verify = cipher.clone();
+        let mut digest = phl::CRC.digest();
+        for chunk in apl.chunks(16) {
+            let mut block = [0; 16];
+            let block = &mut block[..chunk.len()];
+            block.copy_from_slice(chunk);
+            verify.apply_keystream(block);
+            digest.update(block);
+        }
+        if digest.finalize() != crc {
+            return Err(Error::PayloadCrc);
+        }
+
+        cipher.apply_keystream(apl);
+        *payload_crc = crc;
+        Ok(())
+    }
 }
 
 impl<A: Layer> Layer for Ell<A> {
     fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+        // Manufacturer specific CI's are passed through untouched as their data has no standard structure
         let mut offset = 0;
-        if !buffer.is_empty() {
+        if !buffer.is_empty() && !apl::is_manufacturer_specific(buffer[0]) {
             if let Some(header_length) = header_length(buffer[0]) {
                 if buffer.len() < header_length {
                     return Err(Error::Incomplete)?;
@@ -112,18 +290,42 @@ impl<A: Layer> Layer for Ell<A> {
 
     fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError> {
-        if packet.ell.is_some() {
-            todo!()
+        if let Some(fields) = &packet.ell {
+            let header_length = header_length(fields.ci()).unwrap();
+            if writer.remaining_mut() < header_length {
+                return Err(WriteError::BufferTooSmall);
+            }
+
+            writer.put_u8(fields.ci());
+            writer.put_u8(fields.cc().0);
+            writer.put_u8(fields.acc());
+            if let Some(dest) = fields.dest() {
+                writer.put_slice(&dest.get_bytes());
+            }
+            match fields {
+                EllFields::Long {
+                    sn, payload_crc, ..
+                }
+                | EllFields::LongDest {
+                    sn, payload_crc, ..
+                } => {
+                    writer.put_u32_le(*sn);
+                    // The payload CRC is calculated over the application layer if not given
+                    let payload_crc = payload_crc.unwrap_or_else(|| phl::CRC.checksum(&packet.apl));
+                    writer.put_u16_le(payload_crc);
+                }
+                EllFields::Short { .. } | EllFields::ShortDest { .. } => {}
+            }
         }
         self.above.write(writer, packet)?;
         Ok(())
     }
 }
 
-const fn header_length(ci: u8) -> Option<usize> {
+pub(crate) const fn header_length(ci: u8) -> Option<usize> {
     match ci {
         0x8C => Some(1 + 2),
         0x8D => Some(1 + 8),
@@ -132,3 +334,178 @@ const fn header_length(ci: u8) -> Option<usize> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{
+        stack::{apl::Apl, Mode},
+        DeviceType, ManufacturerCode,
+    };
+
+    use super::*;
+
+    #[test]
+    fn can_decode_communication_control() {
+        let cc = CommunicationControl(0x90);
+        assert!(cc.bidirectional());
+        assert!(!cc.response_delay());
+        assert!(!cc.synchronized());
+        assert!(cc.hop_count());
+        assert!(!cc.priority());
+        assert!(!cc.accessibility());
+        assert!(!cc.repeated_access());
+
+        let ell = EllFields::Short { cc: 0x90, acc: 0 };
+        assert_eq!(cc, ell.cc());
+    }
+
+    #[test]
+    fn should_repeat() {
+        let my_address =
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater);
+        let other_address =
+            WMBusAddress::new(ManufacturerCode::KAM, 87654321, 0x01, DeviceType::Heat);
+
+        // Addressed to this node
+        let ell = EllFields::ShortDest {
+            cc: 0x00,
+            acc: 0,
+            dest: my_address.clone(),
+        };
+        assert!(!ell.should_repeat(&my_address));
+
+        // Addressed to another node
+        let ell = EllFields::ShortDest {
+            cc: 0x00,
+            acc: 0,
+            dest: other_address.clone(),
+        };
+        assert!(ell.should_repeat(&my_address));
+
+        // Broadcast
+        let ell = EllFields::Short { cc: 0x00, acc: 0 };
+        assert!(ell.should_repeat(&my_address));
+
+        // Hop count exhausted
+        let ell = EllFields::Short {
+            cc: CommunicationControl::HOP_COUNT,
+            acc: 0,
+        };
+        assert!(!ell.should_repeat(&my_address));
+        // Already repeated
+        let ell = EllFields::Short {
+            cc: CommunicationControl::REPEATED_ACCESS,
+            acc: 0,
+        };
+        assert!(!ell.should_repeat(&my_address));
+        let ell = EllFields::ShortDest {
+            cc: CommunicationControl::BIDIRECTIONAL | CommunicationControl::REPEATED_ACCESS,
+            acc: 0,
+            dest: other_address,
+        };
+        assert!(!ell.should_repeat(&my_address));
+    }
+
+    fn write(fields: EllFields) -> BytesMut {
+        let ell = Ell::new(Apl::new());
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.ell = Some(fields.clone());
+        packet.apl.extend_from_slice(&[0x78, 0x01]).unwrap();
+
+        let mut writer = BytesMut::new();
+        ell.write(&mut writer, &packet).unwrap();
+
+        // The written fields are read back
+        let mut read: Packet = Packet::new(Mode::ModeCFFB);
+        ell.read(&mut read, &writer).unwrap();
+        assert!(Some(fields) == read.ell);
+        assert_eq!(packet.apl, read.apl);
+
+        writer
+    }
+
+    #[test]
+    fn can_write_short() {
+        let writer = write(EllFields::Short {
+            cc: 0x20,
+            acc: 0x01,
+        });
+        assert_eq!(&[0x8C, 0x20, 0x01, 0x78, 0x01], writer.as_ref());
+    }
+
+    #[test]
+    fn can_write_long() {
+        let writer = write(EllFields::Long {
+            cc: 0x20,
+            acc: 0x01,
+            sn: 0x12345678,
+            payload_crc: Some(0xABCD),
+        });
+        assert_eq!(
+            &[0x8D, 0x20, 0x01, 0x78, 0x56, 0x34, 0x12, 0xCD, 0xAB, 0x78, 0x01],
+            writer.as_ref()
+        );
+    }
+
+    #[test]
+    fn can_write_short_dest() {
+        let writer = write(EllFields::ShortDest {
+            cc: 0x20,
+            acc: 0x01,
+            dest: WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        });
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                0x8E, 0x20, 0x01,
+                0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+                0x78, 0x01,
+            ],
+            writer.as_ref()
+        );
+    }
+
+    #[test]
+    fn can_write_long_dest() {
+        let writer = write(EllFields::LongDest {
+            cc: 0x20,
+            acc: 0x01,
+            dest: WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+            sn: 0x12345678,
+            payload_crc: Some(0xABCD),
+        });
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                0x8F, 0x20, 0x01,
+                0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+                0x78, 0x56, 0x34, 0x12, 0xCD, 0xAB,
+                0x78, 0x01,
+            ],
+            writer.as_ref()
+        );
+    }
+
+    #[test]
+    fn can_write_calculated_payload_crc() {
+        let ell = Ell::new(Apl::new());
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.ell = Some(EllFields::Long {
+            cc: 0x20,
+            acc: 0x01,
+            sn: 0x12345678,
+            payload_crc: None,
+        });
+        packet.apl.extend_from_slice(&[0x78, 0x01]).unwrap();
+
+        let mut writer = BytesMut::new();
+        ell.write(&mut writer, &packet).unwrap();
+
+        assert_eq!(
+            &[0x8D, 0x20, 0x01, 0x78, 0x56, 0x34, 0x12, 0xA3, 0x0B, 0x78, 0x01],
+            writer.as_ref()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use core::fmt::Write;
+
+use super::Packet;
+
+impl<const N: usize> Packet<N> {
+    /// Write the packet as a JSON object without allocating.
+    /// The object includes the mode, the rssi, the link layer address and the hex encoded application layer.
+    pub fn write_json(&self, out: &mut impl Write) -> core::fmt::Result {
+        write!(out, "{{\"mode\":\"{}\",\"rssi\":", self.mode.name())?;
+        match self.rssi {
+            Some(rssi) => write!(out, "{}", rssi)?,
+            None => out.write_str("null")?,
+        }
+
+        out.write_str(",\"address\":")?;
+        match &self.dll {
+            Some(dll) => write!(
+                out,
+                "{{\"manufacturer_code\":\"{:#06x}\",\"serial_number\":{},\"version\":{},\"device_type\":{}}}",
+                dll.address.manufacturer_code,
+                dll.address.serial_number(),
+                dll.address.version,
+                dll.address.device_type
+            )?,
+            None => out.write_str("null")?,
+        }
+
+        out.write_str(",\"apl\":\"")?;
+        for byte in self.apl.iter() {
+            write!(out, "{:02x}", byte)?;
+        }
+        out.write_str("\"}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use crate::{
+        stack::{dll::DllFields, Mode},
+        DeviceType, ManufacturerCode, WMBusAddress,
+    };
+
+    use super::*;
+
+    #[test]
+    fn can_write_json() {
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.rssi = Some(-70);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
+        packet.apl.extend_from_slice(&[0xA0, 0x01, 0xFF]).unwrap();
+
+        let mut json = String::<256>::new();
+        packet.write_json(&mut json).unwrap();
+
+        assert!(json.contains(
+            r#""address":{"manufacturer_code":"0x2c2d","serial_number":12345678,"version":1,"device_type":50}"#
+        ));
+        assert_eq!(
+            r#"{"mode":"Mode C FFB","rssi":-70,"address":{"manufacturer_code":"0x2c2d","serial_number":12345678,"version":1,"device_type":50},"apl":"a001ff"}"#,
+            json.as_str()
+        );
+    }
+
+    #[test]
+    fn can_write_json_without_dll() {
+        let packet: Packet = Packet::new(Mode::ModeTMTO);
+
+        let mut json = String::<256>::new();
+        packet.write_json(&mut json).unwrap();
+
+        assert_eq!(
+            r#"{"mode":"Mode T MTO","rssi":null,"address":null,"apl":""}"#,
+            json.as_str()
+        );
+    }
+}
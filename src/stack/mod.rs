@@ -1,17 +1,28 @@
 pub mod apl;
+#[cfg(feature = "alloc")]
+mod dissect;
 pub mod dll;
 pub mod ell;
+mod json;
 pub mod phl;
+pub mod tpl;
 
-use bytes::BytesMut;
+use bytes::BufMut;
 use core::fmt::Debug;
+use core::ops::Range;
 use heapless::Vec;
 
+use crate::{ManufacturerCode, WMBusAddress};
+
+#[cfg(feature = "alloc")]
+pub use dissect::{DissectionNode, DissectionTree};
+
 pub const DEFAULT_APL_MAX: usize = phl::APL_MAX;
 
 /// The Wireless M-Bus protocol stack
 pub struct Stack<A: Layer> {
     pub phl: phl::Phl<dll::Dll<A>>,
+    mode: Option<Mode>,
 }
 
 /// Layer trait
@@ -19,7 +30,7 @@ pub trait Layer {
     fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError>;
     fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError>;
 }
@@ -31,7 +42,7 @@ impl<T: Layer> Layer for &T {
 
     fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError> {
         T::write(self, writer, packet)
@@ -43,15 +54,92 @@ impl<T: Layer> Layer for &T {
 pub struct Packet<const APL_MAX: usize = DEFAULT_APL_MAX> {
     pub frame_len: Option<usize>,
     pub rssi: Option<Rssi>,
+    /// The frequency offset in Hz, if reported by the transceiver
+    pub frequency_offset: Option<i32>,
+    /// The transceiver specific preamble quality, if reported by the transceiver
+    pub preamble_quality: Option<u8>,
+    /// The frequency in Hz that the packet was received on, if reported by the transceiver
+    pub frequency: Option<u32>,
+    /// The number of invalid 3oo6 symbols in a received Mode T frame, see [`crate::modet::symbol_error_count()`]
+    pub symbol_error_count: Option<usize>,
     pub mode: Mode,
     pub phl: Option<phl::PhlFields>,
     pub dll: Option<dll::DllFields>,
     pub ell: Option<ell::EllFields>,
+    /// The transport layer header, if the stack includes a transport layer, see [`Stack::with_tpl()`]
+    pub tpl: Option<tpl::TplFields>,
     pub apl: Vec<u8, APL_MAX>,
 }
 
 pub type Rssi = i16;
 
+/// Options for reading frames that are not received exactly as transmitted, see [`Stack::read_with_options()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadOptions {
+    /// The L field if it is not included in the buffer, e.g. when the radio is configured with a fixed packet length
+    pub l_field: Option<u8>,
+    /// Whether the block CRC's are included in the buffer
+    pub crc: bool,
+    /// Whether the included block CRC's are verified, which may be skipped if they are already verified by the radio
+    pub verify_crc: bool,
+    /// Whether the C field is transmitted before the L field, as done by some legacy meters
+    pub legacy_cl_swap: bool,
+}
+
+impl ReadOptions {
+    /// The L field and the block CRC's are included in the buffer
+    pub const fn new() -> Self {
+        Self {
+            l_field: None,
+            crc: true,
+            verify_crc: true,
+            legacy_cl_swap: false,
+        }
+    }
+
+    /// The L field is not included in the buffer and is supplied by the host
+    pub const fn with_l_field(mut self, l_field: u8) -> Self {
+        self.l_field = Some(l_field);
+        self
+    }
+
+    /// The block CRC's are already removed, e.g. by the radio
+    pub const fn without_crc(mut self) -> Self {
+        self.crc = false;
+        self
+    }
+
+    /// The block CRC's are included but already verified, e.g. by the radio, so they are only removed
+    pub const fn without_crc_verification(mut self) -> Self {
+        self.verify_crc = false;
+        self
+    }
+
+    /// The C field is transmitted before the L field, and the CRC of the first block covers the fields in that order
+    pub const fn with_legacy_cl_swap(mut self) -> Self {
+        self.legacy_cl_swap = true;
+        self
+    }
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The byte ranges consumed by each layer when reading a packet.
+/// The ranges are offsets into the frame data after the block CRC's are removed, i.e. starting with the L field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerMap {
+    /// The L field, the C field and the address
+    pub dll: Range<usize>,
+    pub ell: Option<Range<usize>>,
+    pub tpl: Option<Range<usize>>,
+    pub apl: Range<usize>,
+}
+
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReadError {
@@ -60,11 +148,24 @@ pub enum ReadError {
     Phl(phl::Error),
     Dll(dll::Error),
     Ell(ell::Error),
+    Tpl(tpl::Error),
+    Apl(apl::Error),
+    /// The length of the frame data does not match the data length announced by the L field
+    LengthMismatch,
+    /// The read options cannot be combined, e.g. a supplied L field with the legacy C and L field order
+    IncompatibleOptions,
 }
 
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub enum WriteError {}
+pub enum WriteError {
+    /// The provided buffer is not sufficiently large to include the frame
+    BufferTooSmall,
+    /// The data is too long to be described by the L field of the frame
+    DataTooLong,
+    /// The data is too short to include the mandatory fields of the frame, e.g. the CI field
+    DataTooShort,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -76,6 +177,106 @@ pub enum Mode {
     /// Mode T meter-to-other
     /// Uses frame format A and frame is "three out of six" encoded.
     ModeTMTO,
+    /// Mode T meter-to-other where the frame is already "three out of six" decoded, e.g. by the radio.
+    /// Uses frame format A.
+    ModeTMTODecoded,
+    /// Mode S where the frame is already Manchester decoded, e.g. by the radio.
+    /// Uses frame format A.
+    ModeS,
+    /// Mode T other-to-meter, i.e. the response from a gateway to a Mode T meter.
+    /// The frame is Manchester decoded, e.g. by the radio, and uses frame format A and the Mode S syncword.
+    ModeTOTM,
+}
+
+impl Mode {
+    /// Get all supported modes
+    pub const fn all() -> &'static [Mode] {
+        &[
+            Mode::ModeCFFA,
+            Mode::ModeCFFB,
+            Mode::ModeTMTO,
+            Mode::ModeTMTODecoded,
+            Mode::ModeS,
+            Mode::ModeTOTM,
+        ]
+    }
+
+    /// Get the human readable name of the mode
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Mode::ModeCFFA => "Mode C FFA",
+            Mode::ModeCFFB => "Mode C FFB",
+            Mode::ModeTMTO => "Mode T MTO",
+            Mode::ModeTMTODecoded => "Mode T MTO decoded",
+            Mode::ModeS => "Mode S",
+            Mode::ModeTOTM => "Mode T OTM",
+        }
+    }
+
+    /// Get the mode letter used in interoperability formats and logs, i.e. "C", "T" or "S".
+    /// The letter does not include the frame format or whether the frame is 3oo6 decoded.
+    pub const fn letter(&self) -> &'static str {
+        match self {
+            Mode::ModeCFFA | Mode::ModeCFFB => "C",
+            Mode::ModeTMTO | Mode::ModeTMTODecoded | Mode::ModeTOTM => "T",
+            Mode::ModeS => "S",
+        }
+    }
+
+    /// Get the mode from a mode letter, optionally followed by the meter-to-other direction 1, e.g. "C" or "C1".
+    /// Mode C is assumed to use frame format A, and Mode T frames are assumed to be 3oo6 encoded.
+    pub fn from_letter(letter: &str) -> Option<Mode> {
+        match letter {
+            "C" | "C1" => Some(Mode::ModeCFFA),
+            "T" | "T1" => Some(Mode::ModeTMTO),
+            "S" | "S1" => Some(Mode::ModeS),
+            _ => None,
+        }
+    }
+
+    /// Get the minimum number of received bytes required to derive the frame length in all cases.
+    /// See [`phl::FrameMetadata::read()`] for details.
+    pub const fn derive_length_min(&self) -> usize {
+        match self {
+            Mode::ModeCFFA
+            | Mode::ModeCFFB
+            | Mode::ModeTMTODecoded
+            | Mode::ModeS
+            | Mode::ModeTOTM => phl::DERIVE_FRAME_LENGTH_MIN,
+            Mode::ModeTMTO => phl::MODET_DERIVE_FRAME_LENGTH_MIN,
+        }
+    }
+
+    /// Get the on-air duration in microseconds of a frame with the given length.
+    /// The frame length includes CRC's but excludes any 3oo6 encoding, see [`phl::FrameMetadata::frame_length`].
+    /// The duration includes the minimum preamble, the syncword and the minimum postamble.
+    pub const fn airtime_us(&self, frame_length: usize) -> u32 {
+        let (chips, chiprate) = match self {
+            Mode::ModeCFFA | Mode::ModeCFFB => (
+                crate::modec::PREAMBLE_CHIPS
+                    + crate::modec::SYNCWORD_CHIPS
+                    + 8 * frame_length
+                    + crate::modec::POSTAMBLE_CHIPS,
+                crate::modec::CHIPRATE,
+            ),
+            Mode::ModeTMTO | Mode::ModeTMTODecoded => (
+                crate::modet::PREAMBLE_CHIPS
+                    + crate::modet::SYNCWORD_CHIPS
+                    + 12 * frame_length // Each byte is 3oo6 encoded into 12 chips
+                    + crate::modet::POSTAMBLE_CHIPS,
+                crate::modet::CHIPRATE,
+            ),
+            // The other-to-meter direction of Mode T uses the physical layer of Mode S
+            Mode::ModeS | Mode::ModeTOTM => (
+                crate::modes::PREAMBLE_CHIPS
+                    + crate::modes::SYNCWORD_CHIPS
+                    + crate::modes::CHIPS_PER_BYTE * frame_length
+                    + crate::modes::POSTAMBLE_CHIPS,
+                crate::modes::CHIPRATE,
+            ),
+        };
+        ((chips as u64 * 1_000_000) / chiprate as u64) as u32
+    }
 }
 
 impl<const N: usize> Packet<N> {
@@ -84,10 +285,15 @@ impl<const N: usize> Packet<N> {
         Self {
             frame_len: None,
             rssi: None,
+            frequency_offset: None,
+            preamble_quality: None,
+            frequency: None,
+            symbol_error_count: None,
             mode,
             phl: None,
             dll: None,
             ell: None,
+            tpl: None,
             apl: Vec::new(),
         }
     }
@@ -97,21 +303,296 @@ impl<const N: usize> Packet<N> {
         Self {
             frame_len: None,
             rssi: None,
+            frequency_offset: None,
+            preamble_quality: None,
+            frequency: None,
+            symbol_error_count: None,
             mode,
             phl: None,
             dll: None,
             ell: None,
+            tpl: None,
             apl: Vec::from_slice(&apl).unwrap(),
         }
     }
+
+    /// Get the chain of control information fields in the application layer
+    pub fn ci_chain(&self) -> apl::CiChain<'_> {
+        apl::CiChain::new(&self.apl)
+    }
+
+    /// Get the application CI and its data, i.e. the last CI in the chain
+    pub fn application(&self) -> Option<(u8, &[u8])> {
+        self.ci_chain().last()
+    }
+
+    /// Get the encrypted part of the application layer, if any.
+    /// The returned ciphertext excludes the transport layer header and any trailing unencrypted data,
+    /// and can be stored for later decryption when the key is not available.
+    pub fn ciphertext(&self) -> Option<&[u8]> {
+        if let Some(tpl) = &self.tpl {
+            return apl::encrypted_part(tpl.cw()?, &self.apl);
+        }
+        let (ci, data) = self.application()?;
+        apl::ciphertext(ci, data)
+    }
+
+    /// Decrypt an extended link layer payload that is encrypted with AES-128-CTR, see [`ell::EllFields::is_encrypted()`].
+    /// The application layer is decrypted in place, and the decrypted payload CRC is verified.
+    #[cfg(feature = "crypto")]
+    pub fn decrypt(&mut self, key: &[u8; 16]) -> Result<(), ell::Error> {
+        let dll = self.dll.as_ref().ok_or(ell::Error::NotEncrypted)?;
+        let ell = self.ell.as_mut().ok_or(ell::Error::NotEncrypted)?;
+        ell.decrypt(&dll.raw_address, key, &mut self.apl)
+    }
+
+    /// Decrypt an application layer that is encrypted with AES-128-CBC in security mode 5.
+    /// The encrypted part is decrypted in place, and the key is verified by the 0x2F 0x2F filler that starts the decrypted data.
+    /// The trailing 0x2F fillers that pad the encrypted part to whole blocks are removed from the application layer.
+    /// The security mode of the configuration field is then cleared, so that the packet reads as unencrypted, e.g. by [`Packet::data_records()`].
+    /// The initialization vector is derived from the meter address, i.e. that of a long transport layer header if present, and the access number.
+    #[cfg(feature = "crypto")]
+    pub fn decrypt_mode5(&mut self, key: &[u8; 16]) -> Result<(), apl::DecryptError> {
+        let address = match self.sub_identification() {
+            Some(address) => address.get_bytes(),
+            None => {
+                self.dll
+                    .as_ref()
+                    .ok_or(apl::DecryptError::NotEncrypted)?
+                    .raw_address
+            }
+        };
+
+        let (acc, config, start) = match &self.tpl {
+            Some(tpl) => {
+                let acc = tpl.acc().ok_or(apl::DecryptError::NotEncrypted)?;
+                let config = tpl.cw().ok_or(apl::DecryptError::NotEncrypted)?;
+                (acc, config, 0)
+            }
+            None => {
+                let (ci, data) = self.application().ok_or(apl::DecryptError::NotEncrypted)?;
+                let header_length =
+                    apl::transport_header_length(ci).ok_or(apl::DecryptError::NotEncrypted)?;
+                let header = data
+                    .get(..header_length)
+                    .ok_or(apl::DecryptError::Incomplete)?;
+                let acc = header[header_length - 4];
+                let config =
+                    u16::from_le_bytes([header[header_length - 2], header[header_length - 1]]);
+                (acc, config, self.apl.len() - data.len() + header_length)
+            }
+        };
+
+        let length = apl::decrypt_mode5(key, &address, acc, config, &mut self.apl[start..])?;
+        self.apl.truncate(start + length);
+
+        let config = config & !apl::CONFIG_SECURITY_MODE_MASK;
+        match &mut self.tpl {
+            Some(tpl::TplFields::Short { cw, .. } | tpl::TplFields::Long { cw, .. }) => {
+                *cw = config
+            }
+            // Rejected as not encrypted above
+            Some(tpl::TplFields::NoHeader) => unreachable!(),
+            None => self.apl[start - 2..start].copy_from_slice(&config.to_le_bytes()),
+        }
+        Ok(())
+    }
+
+    /// Get whether the packet is an acknowledgement without application data, e.g. a CNF-IR in a bidirectional exchange.
+    /// This is the case if either the C field or the application CI is that of an acknowledgement.
+    pub fn is_acknowledgement(&self) -> bool {
+        self.dll
+            .as_ref()
+            .is_some_and(|dll| dll.is_acknowledgement())
+            || self
+                .application()
+                .is_some_and(|(ci, _)| apl::is_acknowledgement(ci))
+    }
+
+    /// Get the data records of an unencrypted application payload.
+    /// `None` is returned if the application CI does not carry standard data records,
+    /// e.g. for compact profiles, see [`Packet::compact_profile_data()`].
+    /// The data records of an acknowledgement are always empty, see [`Packet::is_acknowledgement()`].
+    pub fn data_records(&self) -> Option<apl::DataRecords<'_>> {
+        let acknowledgement = self
+            .dll
+            .as_ref()
+            .is_some_and(|dll| dll.is_acknowledgement());
+        apl::data_records(self.tpl.as_ref(), acknowledgement, &self.apl)
+    }
+
+    /// Get the data records of an unencrypted application payload, see [`Packet::data_records()`],
+    /// where the manufacturer specific data is parsed by `handler` for the manufacturer in the data link layer address
+    pub fn data_records_with_handler<'a>(
+        &'a self,
+        handler: &'a dyn apl::ManufacturerHandler,
+    ) -> Option<apl::DataRecords<'a>> {
+        let records = self.data_records()?;
+        Some(match self.manufacturer() {
+            Some(manufacturer) => records.with_manufacturer_handler(manufacturer, handler),
+            None => records,
+        })
+    }
+
+    /// Get the data records of an unencrypted application payload, see [`Packet::data_records()`],
+    /// where manufacturer specific VIF's are decoded by the handler in `registry` for the manufacturer in the data link layer address
+    pub fn data_records_with_registry<'a>(
+        &'a self,
+        registry: apl::records::VifRegistry<'a>,
+    ) -> Option<apl::DataRecords<'a>> {
+        let records = self.data_records()?;
+        Some(match self.manufacturer() {
+            Some(manufacturer) => records.with_vif_registry(manufacturer, registry),
+            None => records,
+        })
+    }
+
+    /// Get the manufacturer in the data link layer address
+    fn manufacturer(&self) -> Option<ManufacturerCode> {
+        self.dll.as_ref().map(|dll| dll.address.manufacturer_code())
+    }
+
+    /// Get the extended status block following the data records, if any, see [`apl::records::EXTENDED_STATUS_DIF`]
+    pub fn extended_status(&self) -> Option<&[u8]> {
+        let mut records = self.data_records()?;
+        records.by_ref().for_each(drop);
+        records.extended_status()
+    }
+
+    /// Get the instantaneous values of the data records grouped by quantity, in the order the quantities first appear.
+    /// Each group contains the storage number and the scaled value of each record with the quantity,
+    /// e.g. the current value at storage number 0 and historical values at higher storage numbers.
+    /// Only records for tariff 0 and subunit 0 are included, and the values are not converted between units.
+    pub fn grouped_records(
+        &self,
+    ) -> impl Iterator<Item = (apl::Quantity, impl Iterator<Item = (u64, f64)> + '_)> + '_ {
+        self.storage_values()
+            .enumerate()
+            .filter(move |(index, (quantity, _, _))| {
+                // Only yield the first occurrence of each quantity
+                !self
+                    .storage_values()
+                    .take(*index)
+                    .any(|(other, _, _)| other == *quantity)
+            })
+            .map(move |(_, (quantity, _, _))| {
+                let values = self
+                    .storage_values()
+                    .filter(move |(other, _, _)| *other == quantity)
+                    .map(|(_, storage_number, value)| (storage_number, value));
+                (quantity, values)
+            })
+    }
+
+    fn storage_values(&self) -> impl Iterator<Item = (apl::Quantity, u64, f64)> + '_ {
+        self.data_records()
+            .into_iter()
+            .flatten()
+            .map_while(Result::ok)
+            .filter(|record| record.dib.is_default_register())
+            .filter_map(|record| {
+                let info = record.value_information()?;
+                let (value, _) = record.scaled_value()?;
+                Some((info.quantity, record.dib.storage_number, value))
+            })
+    }
+
+    /// Get the address and data records of each meter in a data concentrator payload, see [`apl::SubFrames`] for the layout.
+    /// The concentrator CI is vendor specific and is given by `ci`.
+    /// `None` is returned if the application CI is not `ci`.
+    #[cfg(feature = "alloc")]
+    pub fn concentrator_frames(
+        &self,
+        ci: u8,
+    ) -> Option<Result<alloc::vec::Vec<(WMBusAddress, apl::DataRecords<'_>)>, apl::Error>> {
+        let (application_ci, data) = self.application()?;
+        (application_ci == ci).then(|| apl::SubFrames::new(data).collect())
+    }
+
+    /// Get the authentication and fragmentation layer fields, if any
+    pub fn afl(&self) -> Option<apl::AflFields<'_>> {
+        self.ci_chain()
+            .find(|&(ci, _)| ci == apl::AFL_CI)
+            .map(|(_, data)| apl::AflFields::new(data))
+    }
+
+    /// Get the raw data following a compact profile application CI, if any.
+    /// The records are encoded as differences to a reference and must be decoded by a vendor specific decoder.
+    pub fn compact_profile_data(&self) -> Option<&[u8]> {
+        let (ci, data) = self.application()?;
+        apl::is_compact_profile(ci).then_some(data)
+    }
+
+    /// Get the second level identification, i.e. the meter address in the long transport layer header.
+    /// This identifies the actual meter when the data link layer address is that of e.g. a gateway that sends the frame on its behalf.
+    pub fn sub_identification(&self) -> Option<WMBusAddress> {
+        if let Some(tpl) = &self.tpl {
+            return tpl.address().cloned();
+        }
+        let (ci, data) = self.application()?;
+        apl::long_header_address(ci, data)
+    }
+
+    /// Get the status byte of the transport layer header, if any
+    pub fn status(&self) -> Option<u8> {
+        if let Some(tpl) = &self.tpl {
+            return tpl.sts();
+        }
+        let (ci, data) = self.application()?;
+        apl::status(ci, data)
+    }
+
+    /// Get whether the meter has no data available, e.g. because it is busy, so that it should be read again later.
+    /// This is the case if the application status is busy, or if the transport layer header is not followed by any data records.
+    pub fn is_no_data(&self) -> bool {
+        let is_busy =
+            |status| status & apl::STATUS_APPLICATION_MASK == apl::STATUS_APPLICATION_BUSY;
+        if let Some(tpl) = &self.tpl {
+            return tpl.sts().is_some_and(is_busy) || self.apl.is_empty();
+        }
+        let Some((ci, data)) = self.application() else {
+            return false;
+        };
+        if apl::status(ci, data).is_some_and(is_busy) {
+            return true;
+        }
+
+        !self.is_acknowledgement()
+            && apl::ciphertext(ci, data).is_none()
+            && apl::records_offset(ci)
+                .and_then(|offset| data.get(offset..))
+                .is_some_and(<[u8]>::is_empty)
+    }
+
+    /// Get the access number.
+    /// The access number of the transport layer header is preferred, and the access number of the extended link layer is used otherwise.
+    pub fn access_number(&self) -> Option<u8> {
+        if let Some(acc) = self.tpl.as_ref().and_then(|tpl| tpl.acc()) {
+            return Some(acc);
+        }
+        self.application()
+            .and_then(|(ci, data)| apl::access_number(ci, data))
+            .or_else(|| self.ell.as_ref().map(|ell| ell.acc()))
+    }
+
+    /// Get whether the packet was forwarded by a repeater.
+    /// This is the hop count (H) bit in the communication control field of the extended link layer,
+    /// so packets without an extended link layer are never considered repeated.
+    pub fn is_repeated(&self) -> bool {
+        self.ell.as_ref().is_some_and(|ell| ell.cc().hop_count())
+    }
 }
 
 impl Stack<ell::Ell<apl::Apl>> {
     /// Create a new Wireless M-Bus stack
     pub fn new() -> Self {
-        Self {
-            phl: phl::Phl::new(dll::Dll::new(ell::Ell::new(apl::Apl::new()))),
-        }
+        Self::from_phl(phl::Phl::new(dll::Dll::new(ell::Ell::new(apl::Apl::new()))))
+    }
+
+    /// Create a new Wireless M-Bus stack for a gateway that only receives frames in `mode`, e.g. Mode T in much of Europe.
+    /// The frame metadata is then derived for `mode` without the ambiguity between Mode C FFB and Mode T, see [`Stack::read_metadata()`].
+    pub fn for_mode(mode: Mode) -> Self {
+        Self::new().with_mode(mode)
     }
 }
 
@@ -121,38 +602,271 @@ impl Default for Stack<ell::Ell<apl::Apl>> {
     }
 }
 
+impl Stack<ell::Ell<tpl::Tpl<apl::Apl>>> {
+    /// Create a new Wireless M-Bus stack with transport layer.
+    /// The CI field without header and the short and long transport layer headers are read into [`Packet::tpl`],
+    /// and the application layer is the data following the header.
+    pub fn with_tpl() -> Self {
+        // The transport layer headers are consumed before the application layer
+        Self::from_phl(phl::Phl::new(dll::Dll::new(ell::Ell::new(tpl::Tpl::new(
+            apl::Apl::with_min_data_lengths(&[]),
+        )))))
+    }
+}
+
 impl Stack<apl::Apl> {
     /// Create a new Wireless M-Bus stack without extended link layer
     pub fn without_ell() -> Self {
-        Self {
-            phl: phl::Phl::new(dll::Dll::new(apl::Apl::new())),
-        }
+        Self::from_phl(phl::Phl::new(dll::Dll::new(apl::Apl::new())))
     }
 }
 
 impl<A: Layer> Stack<A> {
+    /// Create a new Wireless M-Bus stack with a custom physical layer, e.g. with other layers above the data link layer
+    pub const fn from_phl(phl: phl::Phl<dll::Dll<A>>) -> Self {
+        Self { phl, mode: None }
+    }
+
+    /// Only receive frames in `mode`, see [`Stack::for_mode()`]
+    pub const fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
     /// Read a packet from a byte buffer
     pub fn read(&self, buffer: &[u8], mode: Mode) -> Result<Packet, ReadError> {
+        self.read_with_options(buffer, mode, ReadOptions::new())
+    }
+
+    /// Get the mode of a stack that only receives frames in a single mode, see [`Stack::for_mode()`]
+    pub const fn mode(&self) -> Option<Mode> {
+        self.mode
+    }
+
+    /// Read the frame metadata from the first received bytes.
+    /// This is [`phl::FrameMetadata::read_for_mode()`] for a stack with a single mode, and [`phl::FrameMetadata::read()`] otherwise.
+    pub fn read_metadata(&self, buffer: &[u8]) -> Result<phl::FrameMetadata, phl::Error> {
+        match self.mode {
+            Some(mode) => phl::FrameMetadata::read_for_mode(buffer, mode),
+            None => phl::FrameMetadata::read(buffer),
+        }
+    }
+
+    /// Read a packet from a byte buffer with a frame length that is already known, e.g. from [`phl::FrameMetadata`].
+    /// The buffer starts with the L field, and `frame_length` includes the CRC's but excludes any 3oo6 encoding.
+    /// The frame length is trusted and not derived from the L field, so only the block CRC's and the layers are read.
+    /// Any bytes following the frame, e.g. from the next frame in a capture, are not read.
+    pub fn read_exact(
+        &self,
+        buffer: &[u8],
+        mode: Mode,
+        frame_length: usize,
+    ) -> Result<Packet, ReadError> {
+        let buffer_length = match mode {
+            Mode::ModeTMTO => phl::modet_encoded_bits(frame_length)?.div_ceil(8),
+            Mode::ModeCFFA
+            | Mode::ModeCFFB
+            | Mode::ModeTMTODecoded
+            | Mode::ModeS
+            | Mode::ModeTOTM => frame_length,
+        };
+        let buffer = buffer.get(..buffer_length).ok_or(ReadError::Incomplete)?;
+
+        let mut packet = Packet::new(mode);
+        packet.frame_len = Some(buffer.len());
+        self.phl
+            .read_frame_exact(&mut packet, buffer, frame_length, phl::Verify::Abort)?;
+        Ok(packet)
+    }
+
+    /// Read a packet from a byte buffer that is missing the L field or the block CRC's as specified by `options`.
+    /// The CRC of the first block includes the L field, so a supplied L field must match the transmitted one.
+    /// For [`Mode::ModeTMTO`] the options apply to the frame after the 3oo6 decoding.
+    /// A supplied L field cannot be combined with the legacy C and L field order, as there is no L field to swap.
+    pub fn read_with_options(
+        &self,
+        buffer: &[u8],
+        mode: Mode,
+        options: ReadOptions,
+    ) -> Result<Packet, ReadError> {
+        if options.l_field.is_some() && options.legacy_cl_swap {
+            return Err(ReadError::IncompatibleOptions);
+        }
+        if mode == Mode::ModeTMTO && options != ReadOptions::new() {
+            // The frame length cannot be derived without the L field, so all complete bytes are decoded
+            let mut decode_buf = [0; phl::FRAME_MAX];
+            let decoded = phl::decode_modet_bytes(buffer, &mut decode_buf)?;
+            let mut packet = self.read_with_options(decoded, Mode::ModeTMTODecoded, options)?;
+            packet.mode = Mode::ModeTMTO;
+            packet.frame_len = Some(buffer.len());
+            packet.symbol_error_count = Some(crate::modet::symbol_error_count(buffer));
+            return Ok(packet);
+        }
+
+        let mut frame = [0; phl::FRAME_MAX];
+        let buffer = match options.l_field {
+            _ if options.legacy_cl_swap => {
+                phl::swap_legacy_cl(buffer, mode, options.crc, &mut frame)?
+            }
+            Some(l_field) => {
+                let frame = frame
+                    .get_mut(..1 + buffer.len())
+                    .ok_or(ReadError::Capacity)?;
+                frame[0] = l_field;
+                frame[1..].copy_from_slice(buffer);
+                frame
+            }
+            None => buffer,
+        };
+
+        let mut packet = Packet::new(mode);
+        packet.frame_len = Some(buffer.len());
+        let data_length = if options.crc {
+            let verify = if options.verify_crc {
+                phl::Verify::Abort
+            } else {
+                phl::Verify::Skip
+            };
+            self.phl.read_frame(&mut packet, buffer, verify)?
+        } else {
+            self.phl.read_without_crc(&mut packet, buffer)?
+        };
+
+        if let Some(phl) = &packet.phl {
+            if data_length != phl::data_length(mode, phl.l_field) {
+                return Err(ReadError::LengthMismatch);
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// Read a packet from a byte buffer like [`Stack::read()`], but without failing on invalid block CRC's.
+    /// The packet is assembled from all blocks, and the indices of the blocks with an invalid CRC are returned with it.
+    pub fn read_best_effort(
+        &self,
+        buffer: &[u8],
+        mode: Mode,
+    ) -> Result<(Packet, phl::FailedBlocks), ReadError> {
+        let mut packet = Packet::new(mode);
+        packet.frame_len = Some(buffer.len());
+        let mut failed = phl::FailedBlocks::new();
+        let data_length =
+            self.phl
+                .read_frame(&mut packet, buffer, phl::Verify::Collect(&mut failed))?;
+
+        if let Some(phl) = &packet.phl {
+            if data_length != phl::data_length(mode, phl.l_field) {
+                return Err(ReadError::LengthMismatch);
+            }
+        }
+
+        Ok((packet, failed))
+    }
+
+    /// Read the leading blocks with a valid CRC from a byte buffer, e.g. when the end of a frame collided with another transmission.
+    /// The packet is assembled from the blocks before the first block with an invalid CRC, and the first block must be valid.
+    pub fn read_salvageable(&self, buffer: &[u8], mode: Mode) -> Result<Packet, ReadError> {
         let mut packet = Packet::new(mode);
         packet.frame_len = Some(buffer.len());
-        self.phl.read(&mut packet, buffer)?;
+        self.phl
+            .read_frame(&mut packet, buffer, phl::Verify::Truncate)?;
         Ok(packet)
     }
 
+    /// Use `block_size` data bytes in the optional frame format A blocks instead of the default 16,
+    /// for manufacturers that deviate from EN13757
+    pub fn with_ffa_block_size(mut self, block_size: usize) -> Self {
+        self.phl.set_ffa_block_size(block_size);
+        self
+    }
+
+    /// Read a packet from a byte buffer together with the byte ranges consumed by each layer
+    pub fn read_with_map(
+        &self,
+        buffer: &[u8],
+        mode: Mode,
+    ) -> Result<(Packet, LayerMap), ReadError> {
+        let packet = self.read(buffer, mode)?;
+        let map = layer_map(&packet);
+        Ok((packet, map))
+    }
+
     /// Write a packet
     pub fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError> {
         self.phl.write(writer, packet)
     }
+
+    /// Write a packet into a provided buffer and return the number of bytes written.
+    /// This does not allocate and is suitable for e.g. writing directly into a DMA transmit buffer.
+    pub fn write_to_slice<const N: usize>(
+        &self,
+        out: &mut [u8],
+        packet: &Packet<N>,
+    ) -> Result<usize, WriteError> {
+        let capacity = out.len();
+        let mut writer = &mut *out;
+        self.write(&mut writer, packet)?;
+        let written = capacity - writer.len();
+        debug_assert_eq!(
+            Ok(()),
+            phl::verify_crc_with_block_size(
+                &out[..written],
+                packet.mode,
+                self.phl.ffa_block_size()
+            )
+        );
+        Ok(written)
+    }
+
+    /// Re-frame a packet into a different mode, e.g. when relaying packets received as Mode T to a Mode C collector.
+    /// The link layer frame is unchanged, only the frame format and encoding changes.
+    pub fn relay<const N: usize>(
+        &self,
+        packet: &Packet<N>,
+        target_mode: Mode,
+        writer: &mut impl BufMut,
+    ) -> Result<(), WriteError> {
+        let mut packet = packet.clone();
+        packet.mode = target_mode;
+        self.write(writer, &packet)
+    }
+}
+
+/// Get the byte ranges consumed by each layer of a read packet
+fn layer_map<const N: usize>(packet: &Packet<N>) -> LayerMap {
+    let dll = 0..dll::HEADER_LENGTH;
+    let ell = packet
+        .ell
+        .as_ref()
+        .map(|ell| dll.end..dll.end + ell::header_length(ell.ci()).unwrap());
+    let tpl_start = ell.as_ref().map_or(dll.end, |ell| ell.end);
+    let tpl = packet
+        .tpl
+        .as_ref()
+        .map(|tpl| tpl_start..tpl_start + tpl::header_length(tpl.ci()).unwrap());
+    let apl_start = tpl.as_ref().map_or(tpl_start, |tpl| tpl.end);
+    let apl = apl_start..apl_start + packet.apl.len();
+    LayerMap { dll, ell, tpl, apl }
 }
 
 #[cfg(test)]
 mod tests {
+    use bitvec::prelude::*;
+    use bytes::BytesMut;
+
     use crate::{
-        stack::{dll::DllFields, phl::FrameMetadata},
+        modet::threeoutofsix::ThreeOutOfSix,
+        stack::{
+            apl::records::{Unit, ValueInformation, VifRegistry},
+            dll::DllFields,
+            ell::EllFields,
+            phl::{FrameFormat, FrameMetadata},
+        },
         DeviceType, ManufacturerCode, WMBusAddress,
     };
 
@@ -201,10 +915,10 @@ mod tests {
         let stack = Stack::without_ell();
 
         let mut packet: Packet = Packet::new(Mode::ModeCFFB);
-        packet.dll = Some(DllFields {
-            control: 0x44,
-            address: WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
-        });
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
         packet
             .apl
             .extend_from_slice(&[0xa0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08])
@@ -229,10 +943,10 @@ mod tests {
         let stack = Stack::without_ell();
 
         let mut packet: Packet = Packet::new(Mode::ModeCFFB);
-        packet.dll = Some(DllFields {
-            control: 0x44,
-            address: WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
-        });
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
         packet
             .apl
             .extend_from_slice(&[
@@ -271,4 +985,1131 @@ mod tests {
 
         stack.read(&writer, Mode::ModeCFFB).unwrap();
     }
+
+    #[test]
+    fn can_write_modecffa_three_blocks() {
+        let stack = Stack::without_ell();
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFA);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
+        packet
+            .apl
+            .extend_from_slice(&[
+                0xa0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+                0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+            ])
+            .unwrap();
+
+        let mut writer = BytesMut::new();
+        stack.write(&mut writer, &packet).unwrap();
+
+        assert_eq!(
+            &[
+                0x20, 0x44, 0x2d, 0x2c, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xec, 0x90, 0xa0, 0x00,
+                0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+                0xfc, 0x6f, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x89, 0xee,
+            ],
+            writer.to_vec().as_slice()
+        );
+
+        let read = stack.read(&writer, Mode::ModeCFFA).unwrap();
+        assert_eq!(packet.dll.unwrap().address, read.dll.unwrap().address);
+        assert_eq!(packet.apl, read.apl);
+    }
+
+    #[test]
+    fn can_write_ell_short() {
+        let stack = Stack::new();
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
+        packet.ell = Some(EllFields::Short {
+            cc: 0x20,
+            acc: 0x01,
+        });
+        packet
+            .apl
+            .extend_from_slice(&[0xa0, 0x00, 0x01, 0x02])
+            .unwrap();
+
+        let mut writer = BytesMut::new();
+        stack.write(&mut writer, &packet).unwrap();
+
+        assert_eq!(
+            &[
+                0x12, 0x44, 0x2d, 0x2c, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0x8c, 0x20, 0x01, 0xa0,
+                0x00, 0x01, 0x02, 0x7a, 0x5c,
+            ],
+            writer.to_vec().as_slice()
+        );
+
+        let read = stack.read(&writer, Mode::ModeCFFB).unwrap();
+        assert!(packet.ell == read.ell);
+        assert_eq!(packet.apl, read.apl);
+    }
+
+    #[test]
+    fn can_write_modecffb_to_slice() {
+        let stack = Stack::without_ell();
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
+        packet
+            .apl
+            .extend_from_slice(&[0xa0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06])
+            .unwrap();
+
+        let mut out = [0; 64];
+        let written = stack.write_to_slice(&mut out, &packet).unwrap();
+
+        assert_eq!(
+            &[
+                0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xA0, 0x00, 0x01, 0x02,
+                0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+            ],
+            &out[..written]
+        );
+        assert_eq!(
+            Err(WriteError::BufferTooSmall),
+            stack.write_to_slice(&mut out[..written - 1], &packet)
+        );
+    }
+
+    #[test]
+    fn can_get_compact_profile_data() {
+        let packet: Packet<5> = Packet::with_apl(Mode::ModeCFFB, [0x69, 0x01, 0x02, 0x03, 0x04]);
+
+        assert_eq!(
+            Some([0x01, 0x02, 0x03, 0x04].as_slice()),
+            packet.compact_profile_data()
+        );
+        assert!(packet.data_records().is_none());
+    }
+
+    #[test]
+    fn can_get_data_records() {
+        let packet: Packet<11> = Packet::with_apl(
+            Mode::ModeCFFB,
+            [
+                0x7A, 0x01, 0x00, 0x00, 0x00, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00,
+            ],
+        );
+
+        assert_eq!(None, packet.compact_profile_data());
+        let record = packet.data_records().unwrap().next().unwrap().unwrap();
+        assert_eq!(0x13, record.vib.vif);
+    }
+
+    #[test]
+    fn can_get_data_records_with_registry() {
+        fn decode_kamstrup(vife: &[u8]) -> Option<ValueInformation> {
+            match vife.first()? & 0x7F {
+                // Forward energy in m3 x C
+                0x07 => Some(ValueInformation {
+                    quantity: apl::Quantity::Energy,
+                    unit: Unit::None,
+                    exponent: 0,
+                }),
+                _ => None,
+            }
+        }
+        const REGISTRY: VifRegistry = VifRegistry::new(&[(ManufacturerCode::KAM, decode_kamstrup)]);
+        #[rustfmt::skip]
+        let data = [
+            0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04,
+            0x78,
+            0x04, 0x06, 0xA4, 0x1A, 0x00, 0x00, // Energy 6820kWh
+            0x04, 0xFF, 0x07, 0x4D, 0x6E, 0x03, 0x00, // Forward energy
+            0x02, 0x59, 0x2B, 0x17, // Flow temperature 59.31C
+        ];
+        let mut frame = BytesMut::new();
+        phl::write_ffa_frame(&mut frame, &data).unwrap();
+        let packet = Stack::without_ell().read(&frame, Mode::ModeCFFA).unwrap();
+
+        let forward_energy = packet
+            .data_records_with_registry(REGISTRY)
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Some(apl::Quantity::Energy),
+            forward_energy.value_information().map(|vi| vi.quantity)
+        );
+
+        // The registry has no handler for the manufacturer
+        let mut packet = packet;
+        packet.dll.as_mut().unwrap().address.manufacturer_code = ManufacturerCode::DME.code();
+        let forward_energy = packet
+            .data_records_with_registry(REGISTRY)
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(None, forward_energy.value_information());
+    }
+
+    #[test]
+    fn can_get_extended_status() {
+        #[rustfmt::skip]
+        let packet: Packet<15> = Packet::with_apl(Mode::ModeCFFB, [
+            0x7A, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume 42l
+            0x3F, 0x01, 0x80, 0x00, // Extended status block
+        ]);
+
+        assert_eq!(1, packet.data_records().unwrap().count());
+        assert_eq!(
+            Some([0x01, 0x80, 0x00].as_slice()),
+            packet.extended_status()
+        );
+    }
+
+    #[test]
+    fn can_group_records() {
+        #[rustfmt::skip]
+        let packet: Packet<19> = Packet::with_apl(Mode::ModeCFFB, [
+            0x78,
+            0x04, 0x13, 0x2A, 0x00, 0x00, 0x00, // Volume 42l
+            0x44, 0x13, 0x20, 0x00, 0x00, 0x00, // Volume 32l at storage number 1
+            0x04, 0x06, 0x01, 0x00, 0x00, 0x00, // Energy 1kWh
+        ]);
+
+        let mut groups = packet.grouped_records();
+
+        let (quantity, values) = groups.next().unwrap();
+        assert_eq!(apl::Quantity::Volume, quantity);
+        assert_eq!(
+            [(0, 0.042), (1, 0.032)].as_slice(),
+            values.collect::<Vec<_, 4>>().as_slice()
+        );
+
+        let (quantity, values) = groups.next().unwrap();
+        assert_eq!(apl::Quantity::Energy, quantity);
+        assert_eq!(1, values.count());
+
+        assert!(groups.next().is_none());
+    }
+
+    #[test]
+    fn can_get_access_number() {
+        let mut packet: Packet<5> =
+            Packet::with_apl(Mode::ModeCFFB, [0x7A, 0x2A, 0x00, 0x00, 0x00]);
+        packet.ell = Some(EllFields::Short {
+            cc: 0x00,
+            acc: 0x11,
+        });
+        assert_eq!(Some(0x2A), packet.access_number());
+
+        // No transport layer header
+        let mut packet: Packet<5> =
+            Packet::with_apl(Mode::ModeCFFB, [0x78, 0x04, 0x13, 0x2A, 0x00]);
+        assert_eq!(None, packet.access_number());
+        packet.ell = Some(EllFields::Short {
+            cc: 0x00,
+            acc: 0x11,
+        });
+        assert_eq!(Some(0x11), packet.access_number());
+    }
+
+    #[test]
+    fn can_classify_acknowledgement() {
+        let stack = Stack::default();
+        // CNF-IR with a short transport layer header without application data
+        #[rustfmt::skip]
+        let frame = &[
+            0x10, 0x06, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+            0x8A, 0x01, 0x00, 0x00, 0x00, 0x9E, 0x8D,
+        ];
+
+        let packet = stack.read(frame, Mode::ModeCFFB).unwrap();
+        assert!(packet.is_acknowledgement());
+        assert_eq!(Some(0x01), packet.access_number());
+        assert_eq!(0, packet.data_records().unwrap().count());
+
+        // SND-NR with data records
+        let mut packet: Packet<5> =
+            Packet::with_apl(Mode::ModeCFFB, [0x78, 0x04, 0x13, 0x2A, 0x00]);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        assert!(!packet.is_acknowledgement());
+
+        // ACK with the ACD bit set
+        packet.dll.as_mut().unwrap().control = 0x20;
+        assert!(packet.is_acknowledgement());
+    }
+
+    #[test]
+    fn can_read_with_tpl() {
+        let stack = Stack::with_tpl();
+        #[rustfmt::skip]
+        let frame = &[
+            0x16, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+            0x7A, 0x2A, 0x00, 0x00, 0x00, 0x04, 0x13, 0x39, 0x30, 0x00, 0x00, 0x20, 0x1B,
+        ];
+
+        let (packet, map) = stack.read_with_map(frame, Mode::ModeCFFB).unwrap();
+        assert_eq!(Some(10..15), map.tpl);
+        assert_eq!(15..21, map.apl);
+        assert_eq!(Some(0x2A), packet.access_number());
+        assert_eq!(&frame[15..21], packet.apl.as_slice());
+        let record = packet.data_records().unwrap().next().unwrap().unwrap();
+        assert_eq!(&[0x39, 0x30, 0x00, 0x00], record.data);
+    }
+
+    #[test]
+    fn can_detect_no_data() {
+        let stack = Stack::default();
+        // SND-NR with a short transport layer header where the application is busy
+        #[rustfmt::skip]
+        let frame = &[
+            0x10, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04,
+            0x7A, 0x01, 0x01, 0x00, 0x00, 0x92, 0xE3,
+        ];
+
+        let packet = stack.read(frame, Mode::ModeCFFB).unwrap();
+        assert_eq!(Some(0x01), packet.status());
+        assert!(packet.is_no_data());
+
+        // No data records following the transport layer header
+        let packet: Packet<5> = Packet::with_apl(Mode::ModeCFFB, [0x7A, 0x01, 0x00, 0x00, 0x00]);
+        assert!(packet.is_no_data());
+
+        // Data records
+        let packet: Packet<11> = Packet::with_apl(
+            Mode::ModeCFFB,
+            [
+                0x7A, 0x01, 0x00, 0x00, 0x00, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00,
+            ],
+        );
+        assert!(!packet.is_no_data());
+        let packet: Packet<3> = Packet::with_apl(Mode::ModeCFFB, [0xA0, 0x01, 0x02]);
+        assert!(!packet.is_no_data());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn can_read_concentrator_frames() {
+        let stack = Stack::default();
+        #[rustfmt::skip]
+        let frame = &[
+            0x2A, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xB0,
+            0x0E, 0x2D, 0x2C, 0x11, 0x11, 0x11, 0x11, 0x01, 0x07, 0x04, 0x13, 0x2A, 0x00, 0x00, 0x00,
+            0x0E, 0x2D, 0x2C, 0x22, 0x22, 0x22, 0x22, 0x01, 0x07, 0x04, 0x13, 0x2B, 0x00, 0x00, 0x00,
+            0x4E, 0x0D,
+        ];
+        let packet = stack.read(frame, Mode::ModeCFFB).unwrap();
+
+        let meters = packet.concentrator_frames(0xB0).unwrap().unwrap();
+
+        assert_eq!(2, meters.len());
+        let (address, records) = &meters[0];
+        assert_eq!(11111111, address.serial_number());
+        let record = records.clone().next().unwrap().unwrap();
+        assert_eq!(
+            Some((0.042, apl::records::Unit::CubicMeter)),
+            record.scaled_value()
+        );
+        let (address, records) = &meters[1];
+        assert_eq!(22222222, address.serial_number());
+        assert_eq!(1, records.clone().count());
+
+        assert!(packet.concentrator_frames(0xB1).is_none());
+    }
+
+    #[test]
+    fn can_get_sub_identification() {
+        #[rustfmt::skip]
+        let mut packet: Packet<13> = Packet::with_apl(Mode::ModeCFFB, [
+            0x72,
+            0x78, 0x56, 0x34, 0x12, 0x2D, 0x2C, 0x01, 0x04, // ID, M, V, T
+            0x01, 0x00, 0x00, 0x00, // ACC, STS, CW
+        ]);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 87654321, 0x01, DeviceType::Repeater),
+        ));
+
+        assert_eq!(
+            Some(WMBusAddress::new(
+                ManufacturerCode::KAM,
+                12345678,
+                0x01,
+                DeviceType::Heat
+            )),
+            packet.sub_identification()
+        );
+
+        // Short transport layer header
+        let packet: Packet<5> = Packet::with_apl(Mode::ModeCFFB, [0x7A, 0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(None, packet.sub_identification());
+    }
+
+    #[test]
+    fn can_read_with_map() {
+        let stack = Stack::default();
+        #[rustfmt::skip]
+        let frame = &[
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+
+        let (packet, map) = stack.read_with_map(frame, Mode::ModeCFFB).unwrap();
+
+        assert_eq!(0..10, map.dll);
+        assert_eq!(None, map.ell);
+        assert_eq!(10..18, map.apl);
+        assert_eq!(&frame[map.apl], packet.apl.as_slice());
+    }
+
+    #[test]
+    fn can_verify_written_frame() {
+        let stack = Stack::without_ell();
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFA);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        packet.apl.extend_from_slice(&[0xA0; 20]).unwrap();
+
+        for mode in Mode::all() {
+            packet.mode = *mode;
+            let mut out = [0; 128];
+            let written = stack.write_to_slice(&mut out, &packet).unwrap();
+            let frame = &mut out[..written];
+            assert_eq!(Ok(()), phl::verify_written_frame(frame, *mode));
+
+            // Corrupt the CRC of the last block
+            frame[written - 2] ^= 0x01;
+            assert!(phl::verify_written_frame(frame, *mode).is_err());
+        }
+    }
+
+    #[test]
+    fn can_relay_modetmto_as_modecffa() {
+        let stack = Stack::without_ell();
+
+        let mut packet: Packet = Packet::new(Mode::ModeTMTO);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        packet.apl.extend_from_slice(&[0xA0; 20]).unwrap();
+        let mut writer = BytesMut::new();
+        stack.write(&mut writer, &packet).unwrap();
+        let received = stack.read(&writer, Mode::ModeTMTO).unwrap();
+
+        let mut writer = BytesMut::new();
+        stack.relay(&received, Mode::ModeCFFA, &mut writer).unwrap();
+
+        // 10 + 16 + 4 data bytes in three blocks
+        assert_eq!(10 + 2 + 16 + 2 + 4 + 2, writer.len());
+        assert_eq!(Ok(writer.len()), phl::FFA::get_frame_length(&writer));
+        let relayed = stack.read(&writer, Mode::ModeCFFA).unwrap();
+        assert_eq!(Mode::ModeCFFA, relayed.mode);
+        assert_eq!(received.dll.unwrap().address, relayed.dll.unwrap().address);
+        assert_eq!(received.apl, relayed.apl);
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn can_decrypt_ell_payload() {
+        let encrypted = || {
+            let mut packet: Packet<7> =
+                Packet::with_apl(Mode::ModeCFFB, [0x9A, 0x91, 0x3C, 0x1A, 0x4C, 0x91, 0x95]);
+            packet.dll = Some(DllFields::new(
+                0x44,
+                WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Water),
+            ));
+            packet.ell = Some(EllFields::Long {
+                cc: 0x20,
+                acc: 0x01,
+                sn: 0x20000001,
+                payload_crc: Some(0xB0CB),
+            });
+            packet
+        };
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+
+        let mut packet = encrypted();
+        packet.decrypt(&key).unwrap();
+        assert_eq!(
+            &[0x78, 0x04, 0x13, 0x39, 0x30, 0x00, 0x00],
+            packet.apl.as_slice()
+        );
+        assert!(
+            Some(EllFields::Long {
+                cc: 0x20,
+                acc: 0x01,
+                sn: 0x20000001,
+                payload_crc: Some(0x6CF0),
+            }) == packet.ell
+        );
+
+        // Wrong key, where the payload is left encrypted so the right key can be tried next
+        let mut packet = encrypted();
+        assert_eq!(Err(ell::Error::PayloadCrc), packet.decrypt(&[0; 16]));
+        assert_eq!(encrypted().apl, packet.apl);
+        assert!(encrypted().ell == packet.ell);
+        packet.decrypt(&key).unwrap();
+        assert_eq!(
+            &[0x78, 0x04, 0x13, 0x39, 0x30, 0x00, 0x00],
+            packet.apl.as_slice()
+        );
+
+        // Not encrypted
+        let mut packet = encrypted();
+        packet.ell = Some(EllFields::Short {
+            cc: 0x20,
+            acc: 0x01,
+        });
+        assert_eq!(Err(ell::Error::NotEncrypted), packet.decrypt(&key));
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn can_decrypt_mode5() {
+        // The security mode 5 example of the OMS specification, volume 2, annex N
+        #[rustfmt::skip]
+        const DATA: [u8; 46] = [
+            0x44, 0x93, 0x15, 0x78, 0x56, 0x34, 0x12, 0x33, 0x03,
+            0x7A, 0x2A, 0x00, 0x20, 0x05,
+            0x59, 0x23, 0xC9, 0x5A, 0xAA, 0x26, 0xD1, 0xB2, 0xE7, 0x49, 0x3B, 0x01, 0x3E, 0xC4, 0xA6, 0xF6,
+            0xD3, 0x52, 0x9B, 0x52, 0x0E, 0xDF, 0xF0, 0xEA, 0x6D, 0xEF, 0xC9, 0x9D, 0x6D, 0x69, 0xEB, 0xF3,
+        ];
+        let key = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+            0x0F, 0x11,
+        ];
+        let stack = Stack::without_ell();
+        let encrypted = |data: &[u8]| {
+            let mut frame = BytesMut::new();
+            phl::write_ffa_frame(&mut frame, data).unwrap();
+            stack.read(&frame, Mode::ModeCFFA).unwrap()
+        };
+
+        let mut packet = encrypted(&DATA);
+        assert_eq!(Some(0x2E), packet.phl.as_ref().map(|phl| phl.l_field));
+        packet.decrypt_mode5(&key).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                0x7A, 0x2A, 0x00, 0x20, 0x00,
+                0x2F, 0x2F, 0x0C, 0x14, 0x27, 0x04, 0x85, 0x02, 0x04, 0x6D, 0x32, 0x37, 0x1F, 0x15,
+                0x02, 0xFD, 0x17, 0x00, 0x00,
+            ],
+            packet.apl.as_slice()
+        );
+        assert!(packet.ciphertext().is_none());
+        let record = packet.data_records().unwrap().next().unwrap().unwrap();
+        assert_eq!(&[0x27, 0x04, 0x85, 0x02], record.data);
+
+        // Unencrypted data following the encrypted part is moved to follow the decrypted data
+        let mut data = [0x0F; DATA.len() + 2];
+        data[..DATA.len()].copy_from_slice(&DATA);
+        let mut packet = encrypted(&data);
+        packet.decrypt_mode5(&key).unwrap();
+        assert_eq!(5 + 19 + 2, packet.apl.len());
+        assert_eq!(&[0x00, 0x00, 0x0F, 0x0F], &packet.apl[5 + 17..]);
+
+        // Wrong key, where the data is left encrypted so the right key can be tried next
+        let mut packet = encrypted(&DATA);
+        assert_eq!(
+            Err(apl::DecryptError::WrongKey),
+            packet.decrypt_mode5(&[0; 16])
+        );
+        assert_eq!(encrypted(&DATA).apl, packet.apl);
+        packet.decrypt_mode5(&key).unwrap();
+        assert_eq!(&[0x2F, 0x2F, 0x0C, 0x14], &packet.apl[5..9]);
+
+        // Not encrypted
+        let mut packet = encrypted(&DATA);
+        packet.apl[4] = 0x00;
+        assert_eq!(
+            Err(apl::DecryptError::NotEncrypted),
+            packet.decrypt_mode5(&key)
+        );
+    }
+
+    #[test]
+    fn can_read_legacy_cl_swap() {
+        let stack = Stack::without_ell();
+        #[rustfmt::skip]
+        let frame = [
+            0x44, 0x11, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xEF, 0x29,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x4A, 0x17,
+        ];
+
+        let options = ReadOptions::new().with_legacy_cl_swap();
+        let packet = stack
+            .read_with_options(&frame, Mode::ModeCFFA, options)
+            .unwrap();
+        assert_eq!(Some(0x11), packet.phl.map(|phl| phl.l_field));
+        assert_eq!(0x44, packet.dll.unwrap().control);
+        assert_eq!(
+            &[0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+            packet.apl.as_slice()
+        );
+
+        // The first block CRC is still verified
+        let mut corrupted = frame;
+        corrupted[11] ^= 0xFF;
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(0))),
+            stack
+                .read_with_options(&corrupted, Mode::ModeCFFA, options)
+                .map(|_| ())
+        );
+
+        // The standard order is not accepted
+        assert!(stack.read(&frame, Mode::ModeCFFA).is_err());
+    }
+
+    #[test]
+    fn can_read_metadata_for_mode() {
+        // The first bytes of a 3oo6 encoded Mode T frame with L field 0x1C that may also be Mode C FFB
+        let buffer = [0x37, 0x44, 0xD6];
+
+        // The generic stack must validate the first Mode T block before it can rule out Mode C FFB
+        assert_eq!(
+            Err(phl::Error::Incomplete),
+            Stack::new().read_metadata(&buffer)
+        );
+
+        let stack = Stack::for_mode(Mode::ModeTMTO);
+        assert_eq!(Some(Mode::ModeTMTO), stack.mode());
+        assert_eq!(
+            Ok(FrameMetadata {
+                mode: Mode::ModeTMTO,
+                frame_offset: 0,
+                frame_length: phl::FFA::get_frame_length(&[0x1C]).unwrap(),
+            }),
+            stack.read_metadata(&buffer)
+        );
+
+        // A stack for Mode C does not accept the Mode T frame as Mode C FFA
+        let stack = Stack::for_mode(Mode::ModeCFFA);
+        assert_eq!(
+            Err(phl::Error::Syncword),
+            stack.read_metadata(&[0x54, 0x3D, 0x1C])
+        );
+    }
+
+    #[test]
+    fn can_read_for_mode() {
+        for &mode in Mode::all() {
+            let stack = Stack::for_mode(mode);
+            let mut packet: Packet = Packet::new(mode);
+            packet.dll = Some(DllFields::new(
+                0x44,
+                WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+            ));
+            packet.apl.extend_from_slice(&[0xA0; 20]).unwrap();
+            let mut frame = BytesMut::new();
+            stack.write(&mut frame, &packet).unwrap();
+
+            let metadata = stack.read_metadata(&frame).unwrap();
+            assert_eq!(mode, metadata.mode);
+            let read = stack.read(&frame, metadata.mode).unwrap();
+            assert_eq!(mode, read.mode);
+            assert_eq!(
+                packet.dll.map(|dll| dll.address),
+                read.dll.map(|dll| dll.address)
+            );
+            assert_eq!(packet.apl, read.apl);
+        }
+    }
+
+    #[test]
+    fn can_distinguish_modet_directions() {
+        let stack = Stack::without_ell();
+        let mut packet: Packet = Packet::new(Mode::ModeTOTM);
+        packet.dll = Some(DllFields::new(
+            0x53,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        packet
+            .apl
+            .extend_from_slice(&[0x78, 0x04, 0x13, 0x01, 0x00, 0x00, 0x00])
+            .unwrap();
+
+        let mut otm = [0; 64];
+        otm[..2].copy_from_slice(&crate::modes::SYNCWORD);
+        let written = 2 + stack.write_to_slice(&mut otm[2..], &packet).unwrap();
+        let otm = &otm[..written];
+
+        packet.mode = Mode::ModeTMTO;
+        let mut mto = [0; 64];
+        let written = stack.write_to_slice(&mut mto, &packet).unwrap();
+        let mto = &mto[..written];
+
+        // The meter-to-other direction is 3oo6 encoded
+        assert_eq!(Mode::ModeTMTO, FrameMetadata::read(mto).unwrap().mode);
+        // The other-to-meter direction has the Mode S syncword, so its direction must be known
+        assert_eq!(Mode::ModeS, FrameMetadata::read(otm).unwrap().mode);
+        let metadata = FrameMetadata::read_for_mode(otm, Mode::ModeTOTM).unwrap();
+        assert_eq!(Mode::ModeTOTM, metadata.mode);
+        assert_eq!(2, metadata.frame_offset);
+        assert!(FrameMetadata::read_for_mode(otm, Mode::ModeTMTO).is_err());
+
+        let otm_packet = stack.read(otm, Mode::ModeTOTM).unwrap();
+        let mto_packet = stack.read(mto, Mode::ModeTMTO).unwrap();
+        assert_eq!(Some(0x53), otm_packet.dll.map(|dll| dll.control));
+        assert_eq!(packet.apl, otm_packet.apl);
+        assert_eq!(packet.apl, mto_packet.apl);
+    }
+
+    #[test]
+    fn can_read_exact() {
+        let stack = Stack::default();
+        #[rustfmt::skip]
+        let buffer = [
+            0x54, 0x3D,
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        let metadata = FrameMetadata::read(&buffer).unwrap();
+        let frame = &buffer[metadata.frame_offset..];
+
+        let expected = stack.read(frame, metadata.mode).unwrap();
+        let packet = stack
+            .read_exact(frame, metadata.mode, metadata.frame_length)
+            .unwrap();
+        assert_eq!(expected.dll.unwrap().address, packet.dll.unwrap().address);
+        assert_eq!(expected.apl, packet.apl);
+        assert_eq!(Some(metadata.frame_length), packet.frame_len);
+
+        // The buffer is shorter than the frame length
+        assert_eq!(
+            Err(ReadError::Incomplete),
+            stack
+                .read_exact(frame, metadata.mode, frame.len() + 1)
+                .map(|_| ())
+        );
+
+        // The frame length is too short for the L field
+        assert!(stack
+            .read_exact(frame, metadata.mode, metadata.frame_length - 1)
+            .is_err());
+    }
+
+    #[test]
+    fn can_read_exact_for_mode() {
+        for &mode in Mode::all() {
+            let stack = Stack::for_mode(mode);
+            let mut packet: Packet = Packet::new(mode);
+            packet.dll = Some(DllFields::new(
+                0x44,
+                WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+            ));
+            packet.apl.extend_from_slice(&[0xA0; 40]).unwrap();
+            let mut frame = BytesMut::new();
+            stack.write(&mut frame, &packet).unwrap();
+            frame.extend_from_slice(&[0x00; 4]);
+
+            let metadata = stack.read_metadata(&frame).unwrap();
+            let frame = &frame[metadata.frame_offset..];
+            let expected = stack.read(frame, mode).unwrap();
+            let read = stack
+                .read_exact(frame, mode, metadata.frame_length)
+                .unwrap();
+            assert_eq!(
+                expected.dll.map(|dll| dll.address),
+                read.dll.map(|dll| dll.address)
+            );
+            assert_eq!(expected.apl, read.apl);
+            let symbol_error_count = (mode == Mode::ModeTMTO).then_some(0);
+            assert_eq!(symbol_error_count, expected.symbol_error_count);
+            assert_eq!(symbol_error_count, read.symbol_error_count);
+        }
+    }
+
+    #[test]
+    fn reads_exact_frame_length_without_deriving_it() {
+        // Given a frame format A frame with three optional blocks
+        let stack = Stack::without_ell();
+        let mut data = [0xA0; 9 + 40];
+        data[..9].copy_from_slice(&[0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32]);
+        let mut frame = BytesMut::new();
+        phl::write_ffa_frame(&mut frame, &data).unwrap();
+
+        // When only the first optional block is read
+        let packet = stack.read_exact(&frame, Mode::ModeCFFA, 12 + 18).unwrap();
+
+        // Then the L field is not used to derive the frame length
+        assert_eq!(Some(0x31), packet.phl.map(|phl| phl.l_field));
+        assert_eq!(&[0xA0; 16], packet.apl.as_slice());
+        assert_eq!(
+            Err(ReadError::Incomplete),
+            stack.read(&frame[..12 + 18], Mode::ModeCFFA).map(|_| ())
+        );
+
+        // A trailing block without data is rejected
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::InvalidLength)),
+            stack
+                .read_exact(&frame, Mode::ModeCFFA, 12 + 18 + 2)
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_read_best_effort() {
+        let stack = Stack::without_ell();
+        #[rustfmt::skip]
+        let mut frame = [
+            0x11, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xE9, 0xC7,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x4A, 0x17,
+        ];
+
+        let (packet, failed) = stack.read_best_effort(&frame, Mode::ModeCFFA).unwrap();
+        assert!(failed.is_empty());
+        assert_eq!(0xA0, packet.apl[0]);
+
+        // The second block is corrupted
+        frame[12] = 0xA1;
+        let (packet, failed) = stack.read_best_effort(&frame, Mode::ModeCFFA).unwrap();
+        assert_eq!(&[1], failed.as_slice());
+        assert_eq!(0x44, packet.dll.unwrap().control);
+        assert_eq!(
+            &[0xA1, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+            packet.apl.as_slice()
+        );
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(1))),
+            stack.read(&frame, Mode::ModeCFFA).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_read_best_effort_with_small_ffa_block_size() {
+        let stack = Stack::without_ell().with_ffa_block_size(1);
+        let mut packet: Packet = Packet::new(Mode::ModeCFFA);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        packet.apl.extend_from_slice(&[0xA0; 40]).unwrap();
+        let mut frame = BytesMut::new();
+        stack.write(&mut frame, &packet).unwrap();
+
+        // Corrupt the CRC of all the 40 single byte blocks
+        for block in frame[12..].chunks_mut(3) {
+            block[2] ^= 0xFF;
+        }
+
+        let (read, failed) = stack.read_best_effort(&frame, Mode::ModeCFFA).unwrap();
+        assert_eq!(40, failed.len());
+        assert_eq!(Some(&40), failed.last());
+        assert_eq!(packet.apl, read.apl);
+    }
+
+    #[test]
+    fn can_read_salvageable() {
+        let stack = Stack::without_ell();
+        #[rustfmt::skip]
+        let mut frame = [
+            0x1B, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0x7F, 0x1C,
+            0x78, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x7A, 0x13,
+            0x10, 0x11, 0x7A, 0x93,
+        ];
+        assert!(stack.read(&frame, Mode::ModeCFFA).is_ok());
+
+        // The last block collided with another transmission
+        frame[30..].fill(0xAA);
+        let packet = stack.read_salvageable(&frame, Mode::ModeCFFA).unwrap();
+        assert_eq!(
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+            packet.dll.unwrap().address
+        );
+        assert_eq!(16, packet.apl.len());
+        assert_eq!(0x78, packet.apl[0]);
+
+        // Nothing can be salvaged without a valid header
+        frame[10] ^= 0xFF;
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(0))),
+            stack.read_salvageable(&frame, Mode::ModeCFFA).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_read_with_options() {
+        let stack = Stack::without_ell();
+        #[rustfmt::skip]
+        let frame = [
+            0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xE9, 0xC7,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x4A, 0x17,
+        ];
+        let apl = [0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+
+        // No L field with CRC's
+        let options = ReadOptions::new().with_l_field(0x11);
+        let packet = stack
+            .read_with_options(&frame, Mode::ModeCFFA, options)
+            .unwrap();
+        assert_eq!(0x44, packet.dll.unwrap().control);
+        assert_eq!(&apl, packet.apl.as_slice());
+
+        // The supplied L field is covered by the CRC of the first block
+        let options = ReadOptions::new().with_l_field(0x10);
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(0))),
+            stack
+                .read_with_options(&frame, Mode::ModeCFFA, options)
+                .map(|_| ())
+        );
+
+        // No L field and no CRC's
+        let mut data = [0; 17];
+        data[..9].copy_from_slice(&frame[..9]);
+        data[9..].copy_from_slice(&apl);
+        let options = ReadOptions::new().with_l_field(0x11).without_crc();
+        let packet = stack
+            .read_with_options(&data, Mode::ModeCFFA, options)
+            .unwrap();
+        assert_eq!(&apl, packet.apl.as_slice());
+
+        // No L field with CRC's that are already verified
+        let mut corrupted = frame;
+        corrupted[20] ^= 0xFF;
+        let options = ReadOptions::new()
+            .with_l_field(0x11)
+            .without_crc_verification();
+        let packet = stack
+            .read_with_options(&corrupted, Mode::ModeCFFA, options)
+            .unwrap();
+        assert_eq!(&apl, packet.apl.as_slice());
+    }
+
+    #[test]
+    fn can_read_modetmto_with_options() {
+        let stack = Stack::without_ell();
+        let mut packet: Packet = Packet::new(Mode::ModeTMTO);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        packet.apl.extend_from_slice(&[0xA0, 0x01, 0x02]).unwrap();
+        let mut frame = BytesMut::new();
+        stack.write(&mut frame, &packet).unwrap();
+
+        let options = ReadOptions::new().without_crc_verification();
+        let read = stack
+            .read_with_options(&frame, Mode::ModeTMTO, options)
+            .unwrap();
+        assert_eq!(Mode::ModeTMTO, read.mode);
+        assert_eq!(Some(frame.len()), read.frame_len);
+        assert_eq!(Some(0), read.symbol_error_count);
+        assert_eq!(packet.apl, read.apl);
+
+        // The options apply to the decoded frame, so a corrupted CRC is not verified
+        let mut decode_buf = [0; phl::FRAME_MAX];
+        let decoded_length = phl::decode_modet_bytes(&frame, &mut decode_buf)
+            .unwrap()
+            .len();
+        decode_buf[decoded_length - 1] ^= 0xFF;
+        let mut corrupted = [0; 2 * phl::FRAME_MAX];
+        let bits = ThreeOutOfSix::encode(
+            corrupted.view_bits_mut::<Msb0>(),
+            &decode_buf[..decoded_length],
+        )
+        .unwrap();
+        let corrupted = &corrupted[..bits.div_ceil(8)];
+        let read = stack
+            .read_with_options(corrupted, Mode::ModeTMTO, options)
+            .unwrap();
+        assert_eq!(packet.apl, read.apl);
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(1))),
+            stack.read(corrupted, Mode::ModeTMTO).map(|_| ())
+        );
+
+        assert_eq!(
+            Err(ReadError::IncompatibleOptions),
+            stack
+                .read_with_options(
+                    &frame,
+                    Mode::ModeTMTO,
+                    ReadOptions::new().with_l_field(0x0F).with_legacy_cl_swap()
+                )
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_detect_length_mismatch() {
+        let stack = Stack::without_ell();
+        #[rustfmt::skip]
+        let data = [
+            0x11, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+        ];
+        let options = ReadOptions::new().without_crc();
+        assert!(stack
+            .read_with_options(&data, Mode::ModeCFFA, options)
+            .is_ok());
+
+        // The L field announces more data than present
+        let mut data_too_short = data;
+        data_too_short[0] = 0x12;
+        assert_eq!(
+            Err(ReadError::LengthMismatch),
+            stack
+                .read_with_options(&data_too_short, Mode::ModeCFFA, options)
+                .map(|_| ())
+        );
+
+        // The L field announces less data than present
+        assert_eq!(
+            Err(ReadError::LengthMismatch),
+            stack
+                .read_with_options(&data, Mode::ModeCFFB, options)
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn can_read_modecffa_with_ffa_block_size() {
+        let stack = Stack::without_ell().with_ffa_block_size(8);
+        assert_eq!(16, Stack::without_ell().phl.ffa_block_size());
+
+        // 10 + 8 + 8 + 4 data bytes in four blocks
+        let mut data = [0xA0; 30];
+        data[..10].copy_from_slice(&[0x1D, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x04]);
+        let mut frame = BytesMut::new();
+        for block in [&data[..10], &data[10..18], &data[18..26], &data[26..]] {
+            frame.extend_from_slice(block);
+            frame.extend_from_slice(&phl::CRC.checksum(block).to_be_bytes());
+        }
+
+        let packet = stack.read(&frame, Mode::ModeCFFA).unwrap();
+        assert_eq!(&data[10..], packet.apl.as_slice());
+
+        // The default block size does not match the block CRC's
+        assert_eq!(
+            Err(ReadError::Phl(phl::Error::Crc(1))),
+            Stack::without_ell()
+                .read(&frame, Mode::ModeCFFA)
+                .map(|_| ())
+        );
+
+        // Frames are written with the same block size
+        let mut written = BytesMut::new();
+        stack.write(&mut written, &packet).unwrap();
+        assert_eq!(frame, written);
+    }
+
+    #[test]
+    fn can_convert_mode_letter() {
+        for mode in Mode::all() {
+            let from_letter = Mode::from_letter(mode.letter()).unwrap();
+            assert_eq!(mode.letter(), from_letter.letter());
+        }
+        assert_eq!(Some(Mode::ModeCFFA), Mode::from_letter("C1"));
+        assert_eq!(Some(Mode::ModeTMTO), Mode::from_letter("T1"));
+        assert_eq!(None, Mode::from_letter("C2"));
+        assert_eq!(Some(Mode::ModeS), Mode::from_letter("S1"));
+        assert_eq!(None, Mode::from_letter("N"));
+    }
+
+    #[test]
+    fn can_get_mode_names() {
+        let names: Vec<&str, 6> = Mode::all().iter().map(Mode::name).collect();
+        assert_eq!(
+            [
+                "Mode C FFA",
+                "Mode C FFB",
+                "Mode T MTO",
+                "Mode T MTO decoded",
+                "Mode S",
+                "Mode T OTM"
+            ],
+            names.as_slice()
+        );
+    }
+
+    #[test]
+    fn can_get_airtime() {
+        // 32 + 32 + 20 * 8 + 2 chips at 100kcps
+        assert_eq!(2260, Mode::ModeCFFA.airtime_us(20));
+        assert_eq!(2260, Mode::ModeCFFB.airtime_us(20));
+        // 38 + 10 + 20 * 12 + 2 chips at 100kcps
+        assert_eq!(2900, Mode::ModeTMTO.airtime_us(20));
+        assert_eq!(2900, Mode::ModeTMTODecoded.airtime_us(20));
+        // 30 + 18 + 20 * 16 + 2 chips at 32.768kcps
+        assert_eq!(11291, Mode::ModeS.airtime_us(20));
+    }
+
+    #[test]
+    fn can_get_ciphertext() {
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+        ));
+        // Short transport layer header with security mode 5 and one encrypted block
+        packet
+            .apl
+            .extend_from_slice(&[0x7A, 0x12, 0x00, 0x10, 0x05])
+            .unwrap();
+        let ciphertext = [
+            0x2F, 0x2F, 0xC2, 0x12, 0x4A, 0xF7, 0x5D, 0xB7, 0xE5, 0x0B, 0x19, 0x84, 0x69, 0x29,
+            0xF2, 0xB0,
+        ];
+        packet.apl.extend_from_slice(&ciphertext).unwrap();
+        // Unencrypted trailing data
+        packet.apl.extend_from_slice(&[0x2F, 0x2F]).unwrap();
+
+        let mut writer = BytesMut::new();
+        Stack::without_ell().write(&mut writer, &packet).unwrap();
+        let packet = Stack::default().read(&writer, Mode::ModeCFFB).unwrap();
+
+        assert_eq!(Some(ciphertext.as_slice()), packet.ciphertext());
+        assert_eq!(5 + 16 + 2, packet.apl.len());
+    }
+
+    #[test]
+    fn can_detect_repeated() {
+        let stack = Stack::default();
+
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        packet.dll = Some(DllFields::new(
+            0x44,
+            WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Repeater),
+        ));
+
+        // The original frame
+        packet
+            .apl
+            .extend_from_slice(&[0x8C, 0x00, 0x01, 0x78])
+            .unwrap();
+        let mut writer = BytesMut::new();
+        Stack::without_ell().write(&mut writer, &packet).unwrap();
+        let original = stack.read(&writer, Mode::ModeCFFB).unwrap();
+
+        // The same frame forwarded by a repeater
+        packet.apl[1] |= ell::CommunicationControl::HOP_COUNT;
+        let mut writer = BytesMut::new();
+        Stack::without_ell().write(&mut writer, &packet).unwrap();
+        let repeated = stack.read(&writer, Mode::ModeCFFB).unwrap();
+
+        assert!(!original.is_repeated());
+        assert!(repeated.is_repeated());
+        assert_eq!(original.apl, repeated.apl);
+    }
 }
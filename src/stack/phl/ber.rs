@@ -0,0 +1,73 @@
+use bitvec::prelude::*;
+
+use crate::modet::{threeoutofsix::ThreeOutOfSix, THREE_OUT_OF_SIX_ENCODED_MAX};
+
+use super::FrameFormat;
+use super::FFA;
+
+/// Count the number of bits that differ between a received frame and the expected frame, i.e. the Hamming distance.
+/// Any bytes missing from the shorter of the two frames are counted as 8 bit errors each.
+pub fn bit_error_count(received: &[u8], expected: &[u8]) -> usize {
+    let common = received.len().min(expected.len());
+    let missing = received.len().max(expected.len()) - common;
+    let differing: usize = received
+        .iter()
+        .zip(expected)
+        .map(|(received, expected)| (received ^ expected).count_ones() as usize)
+        .sum();
+    differing + 8 * missing
+}
+
+/// Count the number of 3oo6 symbols that differ between a received Mode T frame before 3oo6 decode,
+/// and the expected frame after 3oo6 decode.
+/// Any symbols missing from the received frame are counted as symbol errors, and any trailing padding bits are ignored.
+pub fn symbol_error_count(received: &[u8], expected: &[u8]) -> usize {
+    assert!(expected.len() <= FFA::FRAME_MAX);
+
+    let mut encoded = [0; THREE_OUT_OF_SIX_ENCODED_MAX];
+    let encoded_bits = ThreeOutOfSix::encode(encoded.view_bits_mut(), expected).unwrap();
+    let expected_symbols = encoded.view_bits::<Msb0>()[..encoded_bits].chunks(6);
+    let received_symbols = received.view_bits::<Msb0>().chunks_exact(6);
+
+    let received_count = received_symbols.len().min(expected_symbols.len());
+    let differing = received_symbols
+        .zip(expected_symbols.clone())
+        .filter(|(received, expected)| received != expected)
+        .count();
+    differing + expected_symbols.len() - received_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_count_bit_errors() {
+        let expected = [0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12];
+
+        assert_eq!(0, bit_error_count(&expected, &expected));
+        assert_eq!(
+            1 + 2 + 8,
+            bit_error_count(&[0x12, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x37, 0xED], &expected)
+        );
+        assert_eq!(16, bit_error_count(&expected[..6], &expected));
+    }
+
+    #[test]
+    fn can_count_symbol_errors() {
+        let expected = [0x13, 0x44, 0x2D, 0x2C];
+        let mut encoded = [0; 6];
+        ThreeOutOfSix::encode(encoded.view_bits_mut(), &expected).unwrap();
+
+        assert_eq!(0, symbol_error_count(&encoded, &expected));
+
+        // Flip a single bit in the first symbol and two bits in the last symbol
+        let mut received = encoded;
+        received[0] ^= 0x80;
+        received[5] ^= 0x03;
+        assert_eq!(2, symbol_error_count(&received, &expected));
+
+        // Truncating the last byte leaves the last two symbols incomplete
+        assert_eq!(2, symbol_error_count(&encoded[..5], &expected));
+    }
+}
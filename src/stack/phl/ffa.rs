@@ -1,14 +1,17 @@
 use heapless::Vec;
 
-use super::is_valid_crc;
 use super::Error;
 use super::FrameFormat;
+use super::Verify;
 
-const FIRST_BLOCK_DATA_LENGTH: usize = 1 + 1 + 2 + 6;
-const OTHER_BLOCK_MAX_DATA_LENGTH: usize = 16;
-const MIN_DATA_LENGTH: usize = FIRST_BLOCK_DATA_LENGTH + 1; // CI field must be present
+pub const FIRST_BLOCK_DATA_LENGTH: usize = 1 + 1 + 2 + 6;
+pub const OTHER_BLOCK_MAX_DATA_LENGTH: usize = 16;
+pub(crate) const MIN_DATA_LENGTH: usize = FIRST_BLOCK_DATA_LENGTH + 1; // CI field must be present
 const MAX_DATA_LENGTH: usize = 256;
-const MAX_BLOCK_COUNT: usize = 17; // 10 + (1 + 15) + 14 * 16 + 6 = 256
+pub(crate) const MAX_BLOCK_COUNT: usize = 17; // 10 + (1 + 15) + 14 * 16 + 6 = 256
+/// The maximum number of blocks for any block size, i.e. for optional blocks with a single data byte
+pub(crate) const MAX_BLOCK_COUNT_ANY_BLOCK_SIZE: usize =
+    1 + (FFA::FRAME_MAX - (FIRST_BLOCK_DATA_LENGTH + 2)) / (1 + 2);
 
 pub struct FFA;
 
@@ -22,48 +25,139 @@ impl FrameFormat for FFA {
             return Err(Error::Incomplete);
         }
 
-        let data_length = 1 + buffer[0] as usize;
-        get_frame_length_from_data_length(data_length)
+        Self::get_frame_length_with_block_size(buffer, OTHER_BLOCK_MAX_DATA_LENGTH)
     }
 
     fn trim_crc(buffer: &[u8]) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
-        let frame_length = Self::get_frame_length(buffer)?;
-        if buffer.len() < frame_length {
+        Self::trim_crc_with_progress(buffer, |_, _| {})
+    }
+}
+
+impl FFA {
+    /// Get the frame length like [`FrameFormat::get_frame_length()`],
+    /// for a frame where the optional blocks have `block_size` data bytes instead of [`OTHER_BLOCK_MAX_DATA_LENGTH`].
+    pub fn get_frame_length_with_block_size(
+        buffer: &[u8],
+        block_size: usize,
+    ) -> Result<usize, Error> {
+        if buffer.is_empty() {
             return Err(Error::Incomplete);
         }
 
-        let (first_block, other_blocks) = buffer.split_at(FIRST_BLOCK_DATA_LENGTH + 2);
+        let data_length = 1 + buffer[0] as usize;
+        get_frame_length_from_data_length(data_length, block_size)
+    }
 
-        // First block
-        if !is_valid_crc(first_block) {
-            return Err(Error::Crc(0));
-        }
+    /// Verify and remove the block CRC's like [`FrameFormat::trim_crc()`],
+    /// for a frame where the optional blocks have `block_size` data bytes instead of [`OTHER_BLOCK_MAX_DATA_LENGTH`].
+    pub fn trim_crc_with_block_size(
+        buffer: &[u8],
+        block_size: usize,
+    ) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
+        trim_blocks(buffer, block_size, Verify::Abort, |_, _| {})
+    }
 
-        let mut data = Vec::from_slice(&first_block[..first_block.len() - 2]).unwrap();
+    /// Remove the block CRC's without verifying them, e.g. when the CRC's are already verified by the radio
+    pub fn trim_crc_no_verify(buffer: &[u8]) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
+        trim_blocks(buffer, OTHER_BLOCK_MAX_DATA_LENGTH, Verify::Skip, |_, _| {})
+    }
 
-        // Subsequent blocks
-        for (index, block) in other_blocks
-            .chunks(OTHER_BLOCK_MAX_DATA_LENGTH + 2)
-            .enumerate()
-        {
-            if !is_valid_crc(block) {
-                return Err(Error::Crc(1 + index));
-            }
-            data.extend_from_slice(&block[..block.len() - 2]).unwrap();
-        }
+    /// Remove the block CRC's without verifying them like [`FFA::trim_crc_no_verify()`],
+    /// for a frame where the optional blocks have `block_size` data bytes instead of [`OTHER_BLOCK_MAX_DATA_LENGTH`].
+    pub fn trim_crc_no_verify_with_block_size(
+        buffer: &[u8],
+        block_size: usize,
+    ) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
+        trim_blocks(buffer, block_size, Verify::Skip, |_, _| {})
+    }
 
-        Ok(data)
+    /// Verify and remove the block CRC's like [`FrameFormat::trim_crc()`],
+    /// and invoke `progress` with `(block_index, total_blocks)` after each block is verified.
+    /// The callback is inlined, so there is no overhead when using [`FrameFormat::trim_crc()`].
+    pub fn trim_crc_with_progress(
+        buffer: &[u8],
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
+        trim_blocks(buffer, OTHER_BLOCK_MAX_DATA_LENGTH, Verify::Abort, progress)
     }
 }
 
-const fn get_frame_length_from_data_length(data_length: usize) -> Result<usize, Error> {
+pub(crate) fn trim_blocks(
+    buffer: &[u8],
+    block_size: usize,
+    verify: Verify,
+    progress: impl FnMut(usize, usize),
+) -> Result<Vec<u8, { FFA::DATA_MAX }>, Error> {
+    let frame_length = FFA::get_frame_length_with_block_size(buffer, block_size)?;
+    trim_blocks_exact(buffer, frame_length, block_size, verify, progress)
+}
+
+/// Remove the block CRC's like [`trim_blocks()`], for a frame with a known `frame_length` that is not derived from the L field
+pub(crate) fn trim_blocks_exact(
+    buffer: &[u8],
+    frame_length: usize,
+    block_size: usize,
+    mut verify: Verify,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Vec<u8, { FFA::DATA_MAX }>, Error> {
+    if frame_length < FIRST_BLOCK_DATA_LENGTH + 2 {
+        return Err(Error::InvalidLength);
+    }
+    if buffer.len() < frame_length {
+        return Err(Error::Incomplete);
+    }
+
+    let (first_block, other_blocks) = buffer.split_at(FIRST_BLOCK_DATA_LENGTH + 2);
+    let other_blocks = &other_blocks[..frame_length - first_block.len()];
+    let total_blocks = 1 + other_blocks.chunks(block_size + 2).len();
+
+    // First block
+    if !verify.check(0, first_block)? {
+        return Err(Error::Crc(0));
+    }
+    progress(0, total_blocks);
+
+    let mut data = Vec::from_slice(&first_block[..first_block.len() - 2]).unwrap();
+
+    // Subsequent blocks
+    for (index, block) in other_blocks.chunks(block_size + 2).enumerate() {
+        // A block must include at least one data byte and its CRC
+        if block.len() < 1 + 2 {
+            return Err(Error::InvalidLength);
+        }
+        if !verify.check(1 + index, block)? {
+            break;
+        }
+        data.extend_from_slice(&block[..block.len() - 2])
+            .map_err(|_| Error::InvalidLength)?;
+        progress(1 + index, total_blocks);
+    }
+
+    Ok(data)
+}
+
+const fn get_frame_length_from_data_length(
+    data_length: usize,
+    block_size: usize,
+) -> Result<usize, Error> {
     if data_length < MIN_DATA_LENGTH {
         return Err(Error::InvalidLength);
     }
 
+    let frame_length = get_blocks_frame_length(data_length, block_size);
+
+    // This can only happen for block sizes smaller than the default
+    if frame_length > FFA::FRAME_MAX {
+        return Err(Error::InvalidLength);
+    }
+
+    Ok(frame_length)
+}
+
+const fn get_blocks_frame_length(data_length: usize, block_size: usize) -> usize {
     let other_data_length = data_length - FIRST_BLOCK_DATA_LENGTH;
-    let full_block_count = other_data_length / OTHER_BLOCK_MAX_DATA_LENGTH;
-    let last_block_data_length = other_data_length - full_block_count * OTHER_BLOCK_MAX_DATA_LENGTH;
+    let full_block_count = other_data_length / block_size;
+    let last_block_data_length = other_data_length - full_block_count * block_size;
 
     let last_block_frame_length = if last_block_data_length > 0 {
         last_block_data_length + 2
@@ -71,14 +165,7 @@ const fn get_frame_length_from_data_length(data_length: usize) -> Result<usize,
         0
     };
 
-    let frame_length = FIRST_BLOCK_DATA_LENGTH
-        + 2
-        + full_block_count * (OTHER_BLOCK_MAX_DATA_LENGTH + 2)
-        + last_block_frame_length;
-
-    debug_assert!(frame_length <= FFA::FRAME_MAX);
-
-    Ok(frame_length)
+    FIRST_BLOCK_DATA_LENGTH + 2 + full_block_count * (block_size + 2) + last_block_frame_length
 }
 
 #[cfg(test)]
@@ -87,43 +174,91 @@ mod tests {
 
     #[test]
     fn can_get_frame_length() {
-        assert!(get_frame_length_from_data_length(0).is_err());
-        assert!(get_frame_length_from_data_length(10).is_err());
+        assert!(get_frame_length_from_data_length(0, OTHER_BLOCK_MAX_DATA_LENGTH).is_err());
+        assert!(get_frame_length_from_data_length(10, OTHER_BLOCK_MAX_DATA_LENGTH).is_err());
         assert_eq!(
             Ok(10 + 2 + 1 + 2),
-            get_frame_length_from_data_length(10 + 1)
+            get_frame_length_from_data_length(10 + 1, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 16 + 2),
-            get_frame_length_from_data_length(10 + 16)
+            get_frame_length_from_data_length(10 + 16, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 16 + 2 + 1 + 2),
-            get_frame_length_from_data_length(10 + 16 + 1)
+            get_frame_length_from_data_length(10 + 16 + 1, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 2 * (16 + 2)),
-            get_frame_length_from_data_length(10 + 2 * 16)
+            get_frame_length_from_data_length(10 + 2 * 16, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 2 * (16 + 2) + 1 + 2),
-            get_frame_length_from_data_length(10 + 2 * 16 + 1)
+            get_frame_length_from_data_length(10 + 2 * 16 + 1, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 3 * (16 + 2)),
-            get_frame_length_from_data_length(10 + 3 * 16)
+            get_frame_length_from_data_length(10 + 3 * 16, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 15 * (16 + 2)),
-            get_frame_length_from_data_length(10 + 15 * 16)
+            get_frame_length_from_data_length(10 + 15 * 16, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 15 * (16 + 2) + 1 + 2),
-            get_frame_length_from_data_length(10 + 15 * 16 + 1)
+            get_frame_length_from_data_length(10 + 15 * 16 + 1, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
         assert_eq!(
             Ok(10 + 2 + 15 * (16 + 2) + 5 + 2),
-            get_frame_length_from_data_length(10 + 15 * 16 + 5)
+            get_frame_length_from_data_length(10 + 15 * 16 + 5, OTHER_BLOCK_MAX_DATA_LENGTH)
         );
     }
+
+    #[test]
+    fn can_trim_crc_without_verification() {
+        let mut frame = [0; FFA::FRAME_MAX];
+        let mut writer = &mut frame[..];
+        super::super::write_ffa_frame(&mut writer, &[0x55; 100]).unwrap();
+        let frame_length = FFA::FRAME_MAX - writer.len();
+        let frame = &mut frame[..frame_length];
+
+        assert_eq!(FFA::trim_crc(frame), FFA::trim_crc_no_verify(frame));
+
+        // The CRC's are not verified
+        frame[frame_length - 1] ^= 0xFF;
+        assert_eq!(Err(Error::Crc(6)), FFA::trim_crc(frame));
+        assert!(FFA::trim_crc_no_verify(frame).is_ok());
+    }
+
+    #[test]
+    fn can_report_progress() {
+        // Given
+        let mut frame = heapless::Vec::<u8, { FFA::FRAME_MAX }>::new();
+        let mut data = [0x55; MAX_DATA_LENGTH];
+        data[0] = (MAX_DATA_LENGTH - 1) as u8;
+        let (first_block, other_blocks) = data.split_at(FIRST_BLOCK_DATA_LENGTH);
+        for block in [first_block]
+            .into_iter()
+            .chain(other_blocks.chunks(OTHER_BLOCK_MAX_DATA_LENGTH))
+        {
+            frame.extend_from_slice(block).unwrap();
+            frame
+                .extend_from_slice(&super::super::CRC.checksum(block).to_be_bytes())
+                .unwrap();
+        }
+
+        // When
+        let mut reported = heapless::Vec::<(usize, usize), MAX_BLOCK_COUNT>::new();
+        let trimmed = FFA::trim_crc_with_progress(&frame, |index, total| {
+            reported.push((index, total)).unwrap()
+        })
+        .unwrap();
+
+        // Then
+        assert_eq!(FFA::FRAME_MAX, frame.len());
+        assert_eq!(data.as_slice(), trimmed.as_slice());
+        assert_eq!(MAX_BLOCK_COUNT, reported.len());
+        assert_eq!((0, 17), reported[0]);
+        assert_eq!((16, 17), reported[16]);
+    }
 }
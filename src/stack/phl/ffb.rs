@@ -1,6 +1,6 @@
-use super::is_valid_crc;
 use super::Error;
 use super::FrameFormat;
+use super::Verify;
 use heapless::Vec;
 
 pub const FIRST_BLOCK_DATA_LENGTH: usize = 1 + 1 + 2 + 6;
@@ -31,23 +31,92 @@ impl FrameFormat for FFB {
     }
 
     fn trim_crc(buffer: &[u8]) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
-        let frame_length = FFB::get_frame_length(buffer)?;
-        if buffer.len() < frame_length {
-            return Err(Error::Incomplete);
-        }
+        trim_blocks(buffer, Verify::Abort)
+    }
+}
+
+impl FFB {
+    /// Remove the block CRC's without verifying them, e.g. when the CRC's are already verified by the radio
+    pub fn trim_crc_no_verify(buffer: &[u8]) -> Result<Vec<u8, { Self::DATA_MAX }>, Error> {
+        trim_blocks(buffer, Verify::Skip)
+    }
+}
+
+pub(crate) fn trim_blocks(
+    buffer: &[u8],
+    verify: Verify,
+) -> Result<Vec<u8, { FFB::DATA_MAX }>, Error> {
+    let frame_length = FFB::get_frame_length(buffer)?;
+    trim_blocks_exact(buffer, frame_length, verify)
+}
+
+/// Remove the block CRC's like [`trim_blocks()`], for a frame with a known `frame_length` that is not derived from the L field
+pub(crate) fn trim_blocks_exact(
+    buffer: &[u8],
+    frame_length: usize,
+    mut verify: Verify,
+) -> Result<Vec<u8, { FFB::DATA_MAX }>, Error> {
+    if frame_length > FFB::FRAME_MAX {
+        return Err(Error::InvalidLength);
+    }
+    if buffer.len() < frame_length {
+        return Err(Error::Incomplete);
+    }
 
-        let mut data = Vec::new();
+    let mut data = Vec::new();
 
-        for (index, block) in buffer
-            .chunks(FIRST_BLOCK_DATA_LENGTH + SECOND_BLOCK_MAX_DATA_LENGTH + 2)
-            .enumerate()
-        {
-            if !is_valid_crc(block) {
-                return Err(Error::Crc(index));
+    // Any bytes following the frame, e.g. padding from a fixed length radio, are ignored
+    for (index, block) in buffer[..frame_length]
+        .chunks(FIRST_BLOCK_DATA_LENGTH + SECOND_BLOCK_MAX_DATA_LENGTH + 2)
+        .enumerate()
+    {
+        // A block must include at least one data byte and its CRC
+        if block.len() < 1 + 2 {
+            return Err(Error::InvalidLength);
+        }
+        if !verify.check(index, block)? {
+            if index == 0 {
+                return Err(Error::Crc(0));
             }
-            data.extend_from_slice(&block[..block.len() - 2]).unwrap();
+            break;
         }
+        data.extend_from_slice(&block[..block.len() - 2]).unwrap();
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_trim_crc_without_verification() {
+        #[rustfmt::skip]
+        let mut frame = [
+            0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+            0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        ];
+
+        assert_eq!(FFB::trim_crc(&frame), FFB::trim_crc_no_verify(&frame));
+
+        // The CRC's are not verified
+        frame[19] ^= 0xFF;
+        assert_eq!(Err(Error::Crc(0)), FFB::trim_crc(&frame));
+        assert!(FFB::trim_crc_no_verify(&frame).is_ok());
+    }
+
+    #[test]
+    fn rejects_trailing_block_without_data() {
+        // The first and second block end after 128 bytes, so L=0x80 leaves a single byte for the optional block
+        let mut frame = [0x55; 1 + 0x80];
+        frame[0] = 0x80;
 
-        Ok(data)
+        assert_eq!(Err(Error::InvalidLength), FFB::trim_crc_no_verify(&frame));
+        let mut failed = super::super::FailedBlocks::new();
+        assert_eq!(
+            Err(Error::InvalidLength),
+            trim_blocks(&frame, Verify::Collect(&mut failed))
+        );
     }
 }
@@ -1,30 +1,50 @@
+mod ber;
 mod ffa;
 mod ffb;
+mod wired;
 
 use bitvec::prelude::*;
-use bytes::{BufMut, BytesMut};
+use bytes::BufMut;
 use crc::{Crc, CRC_16_EN_13757};
 use heapless::Vec;
 
-use crate::modet::threeoutofsix::{self, ThreeOutOfSix};
+use crate::modes;
+use crate::modet::{
+    threeoutofsix::{self, ThreeOutOfSix},
+    THREE_OUT_OF_SIX_ENCODED_MAX,
+};
 
-pub use self::{ffa::FFA, ffb::FFB};
+pub use self::{
+    ber::{bit_error_count, symbol_error_count},
+    ffa::FFA,
+    ffb::FFB,
+    wired::{mbus_checksum, read_wired, write_wired},
+};
 
 use super::{Layer, Mode, Packet, ReadError, WriteError};
 
-const CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_EN_13757);
+pub(crate) const CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_EN_13757);
+
+/// The indices of the blocks with an invalid CRC, where the first block has index 0.
+/// The capacity allows all blocks to fail for any frame format A block size, see [`Phl::set_ffa_block_size()`].
+pub type FailedBlocks = Vec<usize, { ffa::MAX_BLOCK_COUNT_ANY_BLOCK_SIZE }>;
 
 pub const DERIVE_FRAME_LENGTH_MIN: usize = 3;
+/// The number of bytes required to validate the first 3oo6 encoded Mode T block, i.e. 12 bytes including CRC
+pub const MODET_DERIVE_FRAME_LENGTH_MIN: usize = (12 * 6) / 4;
 pub const APL_MAX: usize = FFA::APL_MAX;
 pub const DATA_MAX: usize = FFA::DATA_MAX;
 pub const FRAME_MAX: usize = FFA::FRAME_MAX;
 
 pub struct Phl<A: Layer> {
     above: A,
+    ffa_block_size: usize,
 }
 
 #[derive(Clone)]
-pub struct PhlFields;
+pub struct PhlFields {
+    pub l_field: u8,
+}
 
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -34,6 +54,8 @@ pub enum Error {
     ThreeOutOfSix(threeoutofsix::Error),
     InvalidLength,
     Crc(usize),
+    /// The wired M-Bus checksum is invalid
+    Checksum,
 }
 
 impl From<Error> for ReadError {
@@ -54,6 +76,18 @@ pub trait FrameFormat {
     fn trim_crc(buffer: &[u8]) -> Result<Vec<u8, { Self::DATA_MAX }>, Error>;
 }
 
+/// The policy for classifying a frame that may be either Mode C FFB or Mode T, see [`FrameMetadata::read_with_policy()`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DetectionPolicy {
+    /// The frame is Mode T only if the first block is valid 3oo6 and passes its CRC
+    #[default]
+    RequireCrc,
+    /// The frame is Mode T if the first block is valid 3oo6, even if its CRC fails.
+    /// This may be preferred on noisy links where Mode T is more likely than Mode C FFB.
+    PreferModeT,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct FrameMetadata {
     pub mode: Mode,
@@ -63,13 +97,33 @@ pub struct FrameMetadata {
 }
 
 impl FrameMetadata {
+    /// Read the frame metadata from the first received bytes.
+    ///
+    /// The number of bytes required to derive the frame length depends on the mode, see [`Mode::derive_length_min()`]:
+    /// * Mode C requires [`DERIVE_FRAME_LENGTH_MIN`] bytes, i.e. the last two bytes of the syncword and the L field.
+    /// * Mode T requires [`MODET_DERIVE_FRAME_LENGTH_MIN`] bytes if the first bytes could also be the start of a Mode C FFB frame,
+    ///   as the first block must then be decoded to validate its CRC. Otherwise [`DERIVE_FRAME_LENGTH_MIN`] bytes are sufficient.
+    ///
+    /// [`Error::Incomplete`] is returned if more bytes are required.
     pub fn read(buffer: &[u8]) -> Result<FrameMetadata, Error> {
+        Self::read_with_policy(buffer, DetectionPolicy::RequireCrc)
+    }
+
+    /// Read the frame metadata like [`FrameMetadata::read()`],
+    /// where `policy` decides how a frame that may be either Mode C FFB or Mode T is classified.
+    pub fn read_with_policy(
+        buffer: &[u8],
+        policy: DetectionPolicy,
+    ) -> Result<FrameMetadata, Error> {
         if buffer.len() < DERIVE_FRAME_LENGTH_MIN {
             return Err(Error::Incomplete);
         }
 
         if buffer[0] == 0x54 {
             Self::decode_modec(buffer)
+        } else if buffer.starts_with(&modes::SYNCWORD) {
+            // Mode T other-to-meter has the same syncword, see [`FrameMetadata::read_for_mode()`]
+            Self::decode_modes(buffer, Mode::ModeS)
         } else if buffer[1] == 0x44 {
             // This is very likely a ModeC FFB frame where we have synchronized on the last 16 bits of its syncword 543D_543D.
             // 0x44 is the SND-NR C-field within the frame
@@ -83,7 +137,7 @@ impl FrameMetadata {
                 // If that block passes CRC then it is ModeT, otherwise we assume ModeC FFB
 
                 // The first block is 12 bytes including its CRC - it is 3oo6 encoded so we actually need 18 bytes to proceed
-                if let Some(result) = Self::try_decode_first_modet_block(buffer)? {
+                if let Some(result) = Self::try_decode_first_modet_block(buffer, policy)? {
                     return Ok(result);
                 }
             }
@@ -102,6 +156,64 @@ impl FrameMetadata {
         }
     }
 
+    /// Read the frame metadata of a frame that is known to be received in `mode`.
+    /// Unlike [`FrameMetadata::read()`], a frame that may be either Mode C FFB or Mode T is not ambiguous,
+    /// so a Mode T frame length is derived from [`DERIVE_FRAME_LENGTH_MIN`] bytes without validating the first block.
+    /// This is also the only way to detect Mode T other-to-meter, as it cannot be distinguished from Mode S by its syncword.
+    pub fn read_for_mode(buffer: &[u8], mode: Mode) -> Result<FrameMetadata, Error> {
+        if buffer.len() < DERIVE_FRAME_LENGTH_MIN {
+            return Err(Error::Incomplete);
+        }
+
+        match mode {
+            Mode::ModeTMTO => Self::decode_modet(buffer),
+            Mode::ModeCFFA | Mode::ModeCFFB if buffer[0] == 0x54 => {
+                let metadata = Self::decode_modec(buffer)?;
+                if metadata.mode != mode {
+                    return Err(Error::Syncword);
+                }
+                Ok(metadata)
+            }
+            Mode::ModeCFFB => Ok(FrameMetadata {
+                mode,
+                frame_offset: 0,
+                frame_length: FFB::get_frame_length(buffer)?,
+            }),
+            Mode::ModeS | Mode::ModeTOTM if buffer.starts_with(&modes::SYNCWORD) => {
+                Self::decode_modes(buffer, mode)
+            }
+            Mode::ModeCFFA | Mode::ModeTMTODecoded | Mode::ModeS | Mode::ModeTOTM => {
+                Ok(FrameMetadata {
+                    mode,
+                    frame_offset: 0,
+                    frame_length: FFA::get_frame_length(buffer)?,
+                })
+            }
+        }
+    }
+
+    /// Read the frame metadata from a buffer where the frame is preceded by `prefix` bytes,
+    /// e.g. a status or rssi byte prepended by the radio.
+    /// The returned frame offset is relative to the start of the buffer, i.e. it includes the prefix.
+    pub fn read_skipping(buffer: &[u8], prefix: usize) -> Result<FrameMetadata, Error> {
+        if buffer.len() < prefix {
+            return Err(Error::Incomplete);
+        }
+
+        let mut metadata = Self::read(&buffer[prefix..])?;
+        metadata.frame_offset += prefix;
+        Ok(metadata)
+    }
+
+    fn decode_modes(buffer: &[u8], mode: Mode) -> Result<FrameMetadata, Error> {
+        let frame_length = FFA::get_frame_length(&buffer[modes::SYNCWORD.len()..])?;
+        Ok(FrameMetadata {
+            mode,
+            frame_offset: modes::SYNCWORD.len(),
+            frame_length,
+        })
+    }
+
     fn decode_modec(buffer: &[u8]) -> Result<FrameMetadata, Error> {
         if buffer.len() < 2 {
             return Err(Error::Incomplete);
@@ -129,9 +241,12 @@ impl FrameMetadata {
         }
     }
 
-    fn try_decode_first_modet_block(buffer: &[u8]) -> Result<Option<FrameMetadata>, Error> {
+    fn try_decode_first_modet_block(
+        buffer: &[u8],
+        policy: DetectionPolicy,
+    ) -> Result<Option<FrameMetadata>, Error> {
         // The first block is 12 bytes - it is 3oo6 encoded so we actually need to received 18 bytes.
-        if buffer.len() < (12 * 6) / 4 {
+        if buffer.len() < MODET_DERIVE_FRAME_LENGTH_MIN {
             return Err(Error::Incomplete);
         }
 
@@ -142,7 +257,7 @@ impl FrameMetadata {
 
             assert_eq!(12, decoded);
 
-            if is_valid_crc(&block) {
+            if is_valid_crc(&block) || policy == DetectionPolicy::PreferModeT {
                 let frame_length = FFA::get_frame_length(&block)?;
                 return Ok(Some(FrameMetadata {
                     mode: Mode::ModeTMTO,
@@ -175,86 +290,427 @@ impl FrameMetadata {
 
 impl<A: Layer> Phl<A> {
     pub const fn new(above: A) -> Self {
-        Self { above }
+        Self {
+            above,
+            ffa_block_size: ffa::OTHER_BLOCK_MAX_DATA_LENGTH,
+        }
     }
-}
 
-impl<A: Layer> Layer for Phl<A> {
-    fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+    /// Set the number of data bytes in the optional frame format A blocks, which is 16 unless the manufacturer deviates from EN13757.
+    /// This only applies when reading and writing frames, not when deriving the frame length with [`FrameMetadata::read()`].
+    pub fn set_ffa_block_size(&mut self, block_size: usize) {
+        assert!(block_size > 0);
+        self.ffa_block_size = block_size;
+    }
+
+    /// Get the number of data bytes in the optional frame format A blocks
+    pub const fn ffa_block_size(&self) -> usize {
+        self.ffa_block_size
+    }
+
+    /// Read a frame, where the block CRC's are handled as specified by `verify`.
+    /// The length of the frame data after the block CRC's are removed is returned.
+    pub(crate) fn read_frame<const N: usize>(
+        &self,
+        packet: &mut Packet<N>,
+        buffer: &[u8],
+        verify: Verify,
+    ) -> Result<usize, ReadError> {
         match packet.mode {
             Mode::ModeTMTO => {
-                let mut symbols = (buffer.len() * 8) / 6;
-                symbols &= !1; // The number of symbols must be even
                 let mut decode_buf = [0; FFA::FRAME_MAX];
-                let buffer_bits = buffer.view_bits::<Msb0>();
-                let encoded = &buffer_bits[..6 * symbols];
-                let decoded = ThreeOutOfSix::decode(&mut decode_buf, encoded)
-                    .map_err(Error::ThreeOutOfSix)?;
-                let payload = FFA::trim_crc(&decode_buf[..decoded])?;
-                self.above.read(packet, &payload)
+                let decoded = decode_modet_frame(buffer, &mut decode_buf, self.ffa_block_size)?;
+                let encoded = &buffer[..(2 * 6 * decoded.len()).div_ceil(8)];
+                packet.symbol_error_count = Some(crate::modet::symbol_error_count(encoded));
+                let payload = self.trim_ffa(decoded, verify)?;
+                self.read_without_crc(packet, &payload)
+            }
+            Mode::ModeTMTODecoded => {
+                let payload = self.trim_ffa(buffer, verify)?;
+                self.read_without_crc(packet, &payload)
             }
             Mode::ModeCFFA => {
                 let offset = buffer
                     .starts_with(&[0x54, 0xCD])
                     .then_some(2)
                     .unwrap_or_default();
-                let payload = FFA::trim_crc(&buffer[offset..])?;
-                self.above.read(packet, &payload)
+                let payload = self.trim_ffa(&buffer[offset..], verify)?;
+                self.read_without_crc(packet, &payload)
+            }
+            Mode::ModeS | Mode::ModeTOTM => {
+                let offset = if buffer.starts_with(&modes::SYNCWORD) {
+                    modes::SYNCWORD.len()
+                } else {
+                    0
+                };
+                let payload = self.trim_ffa(&buffer[offset..], verify)?;
+                self.read_without_crc(packet, &payload)
             }
             Mode::ModeCFFB => {
                 let offset = buffer
                     .starts_with(&[0x54, 0x3D])
                     .then_some(2)
                     .unwrap_or_default();
-                let payload = FFB::trim_crc(&buffer[offset..])?;
-                self.above.read(packet, &payload)
+                let payload = ffb::trim_blocks(&buffer[offset..], verify)?;
+                self.read_without_crc(packet, &payload)
+            }
+        }
+    }
+
+    /// Read a frame like [`Phl::read_frame()`], where the frame length is known and not derived from the L field.
+    /// The buffer starts with the L field, and `frame_length` includes the CRC's but excludes any 3oo6 encoding.
+    pub(crate) fn read_frame_exact<const N: usize>(
+        &self,
+        packet: &mut Packet<N>,
+        buffer: &[u8],
+        frame_length: usize,
+        verify: Verify,
+    ) -> Result<usize, ReadError> {
+        match packet.mode {
+            Mode::ModeTMTO => {
+                let encoded = buffer
+                    .view_bits::<Msb0>()
+                    .get(..modet_encoded_bits(frame_length)?)
+                    .ok_or(Error::Incomplete)?;
+                packet.symbol_error_count = Some(crate::modet::symbol_error_count(
+                    &buffer[..encoded.len().div_ceil(8)],
+                ));
+                let mut decode_buf = [0; FFA::FRAME_MAX];
+                let decoded = ThreeOutOfSix::decode(&mut decode_buf, encoded)
+                    .map_err(Error::ThreeOutOfSix)?;
+                let payload = self.trim_ffa_exact(&decode_buf[..decoded], frame_length, verify)?;
+                self.read_without_crc(packet, &payload)
+            }
+            Mode::ModeCFFA | Mode::ModeTMTODecoded | Mode::ModeS | Mode::ModeTOTM => {
+                let payload = self.trim_ffa_exact(buffer, frame_length, verify)?;
+                self.read_without_crc(packet, &payload)
+            }
+            Mode::ModeCFFB => {
+                let payload = ffb::trim_blocks_exact(buffer, frame_length, verify)?;
+                self.read_without_crc(packet, &payload)
             }
         }
     }
 
+    fn trim_ffa(&self, buffer: &[u8], verify: Verify) -> Result<Vec<u8, { FFA::DATA_MAX }>, Error> {
+        ffa::trim_blocks(buffer, self.ffa_block_size, verify, |_, _| {})
+    }
+
+    fn trim_ffa_exact(
+        &self,
+        buffer: &[u8],
+        frame_length: usize,
+        verify: Verify,
+    ) -> Result<Vec<u8, { FFA::DATA_MAX }>, Error> {
+        ffa::trim_blocks_exact(buffer, frame_length, self.ffa_block_size, verify, |_, _| {})
+    }
+
+    /// Read frame data where the block CRC's are already removed, i.e. starting with the L field.
+    /// The length of the frame data is returned.
+    pub(crate) fn read_without_crc<const N: usize>(
+        &self,
+        packet: &mut Packet<N>,
+        buffer: &[u8],
+    ) -> Result<usize, ReadError> {
+        packet.phl = buffer.first().map(|&l_field| PhlFields { l_field });
+        self.above.read(packet, buffer)?;
+        Ok(buffer.len())
+    }
+}
+
+impl<A: Layer> Layer for Phl<A> {
+    fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+        self.read_frame(packet, buffer, Verify::Abort)?;
+        Ok(())
+    }
+
     fn write<const N: usize>(
         &self,
-        writer: &mut BytesMut,
+        writer: &mut impl BufMut,
         packet: &Packet<N>,
     ) -> Result<(), WriteError> {
-        let start = writer.len();
-        writer.put_u8(0x00); // Dummy L field
-        self.above.write(writer, packet)?;
-        let len = writer.len() - start;
-
-        // Write L field
-        writer[start] = if len <= ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH {
-            len + 2 - 1
-        } else {
-            len + 2 + 2 - 1
-        } as u8;
+        // Write the layers above after the L field
+        let mut data = [0; FFA::DATA_MAX];
+        let len = {
+            let mut above = &mut data[1..];
+            self.above.write(&mut above, packet)?;
+            FFA::DATA_MAX - above.len()
+        };
+        let data = &mut data[..len];
 
-        let data = &writer[start..];
+        match packet.mode {
+            Mode::ModeTMTO => {
+                let mut frame = [0; FFA::FRAME_MAX];
+                let frame_length = {
+                    let mut frame = &mut frame[..];
+                    write_ffa(&mut frame, data, self.ffa_block_size)?;
+                    FFA::FRAME_MAX - frame.len()
+                };
+
+                // Each byte is encoded into two 6 bit symbols
+                let mut encoded = [0; THREE_OUT_OF_SIX_ENCODED_MAX];
+                let encoded_bits =
+                    ThreeOutOfSix::encode(encoded.view_bits_mut(), &frame[..frame_length]).unwrap();
+                let encoded = &encoded[..encoded_bits.div_ceil(8)];
+                if writer.remaining_mut() < encoded.len() {
+                    return Err(WriteError::BufferTooSmall);
+                }
+                writer.put_slice(encoded);
+                Ok(())
+            }
+            Mode::ModeCFFA | Mode::ModeTMTODecoded | Mode::ModeS | Mode::ModeTOTM => {
+                write_ffa(writer, data, self.ffa_block_size)
+            }
+            Mode::ModeCFFB => write_ffb(writer, data),
+        }
+    }
+}
 
-        if len <= ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH {
-            let mut digest = CRC.digest();
-            digest.update(data);
-            let crc = digest.finalize();
-            writer.put_u16(crc);
-        } else {
-            // Move the optional block
-            let first_len = ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH;
-            writer.put_u16(0);
-            let written = writer.len();
-            writer.copy_within(start + first_len..written - 2, start + first_len + 2);
-
-            let first_block = &mut writer[start..start + first_len + 2];
-            let mut digest = CRC.digest();
-            digest.update(&first_block[..first_len]);
-            first_block[first_len..].copy_from_slice(&digest.finalize().to_be_bytes());
-
-            let second_data = &writer[start + first_len + 2..];
-            let mut digest = CRC.digest();
-            digest.update(second_data);
-            writer.put_u16(digest.finalize());
+/// Copy a frame where the C field is transmitted before the L field into `out` with the fields in the standard order.
+/// If `crc` is set and the CRC of the first block is valid for the transmitted order, then it is replaced by the CRC for the standard order.
+pub(crate) fn swap_legacy_cl<'a>(
+    buffer: &[u8],
+    mode: Mode,
+    crc: bool,
+    out: &'a mut [u8],
+) -> Result<&'a [u8], ReadError> {
+    if buffer.len() < 2 {
+        return Err(ReadError::Incomplete);
+    }
+    let out = out.get_mut(..buffer.len()).ok_or(ReadError::Capacity)?;
+    out.copy_from_slice(buffer);
+    out.swap(0, 1);
+
+    if crc {
+        let first_block_length = match mode {
+            Mode::ModeCFFA
+            | Mode::ModeTMTO
+            | Mode::ModeTMTODecoded
+            | Mode::ModeS
+            | Mode::ModeTOTM => ffa::FIRST_BLOCK_DATA_LENGTH,
+            // The first and second block share the CRC
+            Mode::ModeCFFB => {
+                (1 + out[0] as usize)
+                    .min(ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH + 2)
+                    - 2
+            }
+        };
+        let first_block = buffer
+            .get(..first_block_length + 2)
+            .ok_or(ReadError::Incomplete)?;
+        if is_valid_crc(first_block) {
+            let crc = CRC.checksum(&out[..first_block_length]);
+            out[first_block_length..first_block_length + 2].copy_from_slice(&crc.to_be_bytes());
         }
+    }
 
-        Ok(())
+    Ok(out)
+}
+
+/// Get the number of data bytes announced by the L field, i.e. the frame length including the L field but excluding the block CRC's
+pub const fn data_length(mode: Mode, l_field: u8) -> usize {
+    let frame_length = 1 + l_field as usize;
+    match mode {
+        Mode::ModeCFFA | Mode::ModeTMTO | Mode::ModeTMTODecoded | Mode::ModeS | Mode::ModeTOTM => {
+            frame_length
+        }
+        // The L field of frame format B includes the CRC's
+        Mode::ModeCFFB => {
+            if frame_length <= ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH + 2
+            {
+                frame_length - 2
+            } else {
+                frame_length - 2 - 2
+            }
+        }
+    }
+}
+
+/// Verify that a frame written by [`Phl`] decodes with valid CRC's, see [`verify_crc()`].
+/// This is a self-check of the write path, e.g. to guard against regressions, and is invoked by [`super::Stack::write_to_slice()`] in debug builds.
+/// The frame must not include the syncword.
+pub fn verify_written_frame(buffer: &[u8], mode: Mode) -> Result<(), Error> {
+    verify_crc(buffer, mode)
+}
+
+/// Verify the block CRC's of a received frame without reading the layers, e.g. to discard corrupted frames early.
+/// The frame must not include the syncword, see [`FrameMetadata::frame_offset`].
+pub fn verify_crc(buffer: &[u8], mode: Mode) -> Result<(), Error> {
+    verify_crc_with_block_size(buffer, mode, ffa::OTHER_BLOCK_MAX_DATA_LENGTH)
+}
+
+/// Verify the block CRC's like [`verify_crc()`], where the optional frame format A blocks have `ffa_block_size` data bytes
+pub(crate) fn verify_crc_with_block_size(
+    buffer: &[u8],
+    mode: Mode,
+    ffa_block_size: usize,
+) -> Result<(), Error> {
+    match mode {
+        Mode::ModeTMTO => {
+            let mut decode_buf = [0; FFA::FRAME_MAX];
+            let decoded = decode_modet_frame(buffer, &mut decode_buf, ffa_block_size)?;
+            FFA::trim_crc_with_block_size(decoded, ffa_block_size)?;
+        }
+        Mode::ModeCFFA | Mode::ModeTMTODecoded | Mode::ModeS | Mode::ModeTOTM => {
+            FFA::trim_crc_with_block_size(buffer, ffa_block_size)?;
+        }
+        Mode::ModeCFFB => {
+            FFB::trim_crc(buffer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decode a 3oo6 encoded Mode T frame, ignoring any trailing padding symbols
+fn decode_modet_frame<'a>(
+    buffer: &[u8],
+    decode_buf: &'a mut [u8; FFA::FRAME_MAX],
+    ffa_block_size: usize,
+) -> Result<&'a [u8], Error> {
+    let buffer_bits = buffer.view_bits::<Msb0>();
+    if buffer_bits.len() < 6 * 2 {
+        return Err(Error::Incomplete);
+    }
+
+    // Derive the frame length from the L field so that any trailing padding symbols are not decoded
+    let mut l_field = [0; 1];
+    ThreeOutOfSix::decode(&mut l_field, &buffer_bits[..6 * 2]).map_err(Error::ThreeOutOfSix)?;
+    // The frame length is at most FFA::FRAME_MAX, so it can always be 3oo6 encoded
+    let frame_length = FFA::get_frame_length_with_block_size(&l_field, ffa_block_size)?;
+    let encoded_bits = 2 * 6 * frame_length;
+    if buffer_bits.len() < encoded_bits {
+        return Err(Error::Incomplete);
+    }
+
+    let encoded = &buffer_bits[..encoded_bits];
+    let decoded = ThreeOutOfSix::decode(decode_buf, encoded).map_err(Error::ThreeOutOfSix)?;
+    Ok(&decode_buf[..decoded])
+}
+
+/// Get the number of bits in a 3oo6 encoded frame of `frame_length` bytes, i.e. two 6 bit symbols per byte.
+/// A frame length that is not derived from the L field is invalid if the encoded frame does not fit in [`THREE_OUT_OF_SIX_ENCODED_MAX`].
+pub(crate) const fn modet_encoded_bits(frame_length: usize) -> Result<usize, Error> {
+    let symbols = 2 * frame_length;
+    if 6 * symbols > 8 * THREE_OUT_OF_SIX_ENCODED_MAX {
+        return Err(Error::InvalidLength);
+    }
+    Ok(6 * symbols)
+}
+
+/// Decode all complete 3oo6 encoded bytes in `buffer`, e.g. for a frame without an L field to derive the frame length from
+pub(crate) fn decode_modet_bytes<'a>(
+    buffer: &[u8],
+    decode_buf: &'a mut [u8; FFA::FRAME_MAX],
+) -> Result<&'a [u8], Error> {
+    let buffer_bits = buffer.view_bits::<Msb0>();
+    // Any trailing bits that do not form a complete byte are padding
+    let encoded = &buffer_bits[..buffer_bits.len() - buffer_bits.len() % (6 * 2)];
+    let decoded = ThreeOutOfSix::decode(decode_buf, encoded).map_err(Error::ThreeOutOfSix)?;
+    Ok(&decode_buf[..decoded])
+}
+
+/// Write `data`, i.e. the C field, the A field, the CI field and any user data, as a frame format A frame.
+/// The L field and the block CRC's are added, e.g. for data received from a radio module that has already removed them.
+pub fn write_ffa_frame(writer: &mut impl BufMut, data: &[u8]) -> Result<(), WriteError> {
+    if data.len() >= FFA::DATA_MAX {
+        return Err(WriteError::DataTooLong);
+    }
+    let mut buf = [0; FFA::DATA_MAX];
+    buf[1..1 + data.len()].copy_from_slice(data);
+    write_ffa(
+        writer,
+        &mut buf[..1 + data.len()],
+        ffa::OTHER_BLOCK_MAX_DATA_LENGTH,
+    )
+}
+
+/// Write the data, including a dummy L field, as a frame format A frame with a CRC after each block
+fn write_ffa(
+    writer: &mut impl BufMut,
+    data: &mut [u8],
+    block_size: usize,
+) -> Result<(), WriteError> {
+    if data.len() < ffa::MIN_DATA_LENGTH {
+        return Err(WriteError::DataTooShort);
+    }
+
+    // Write L field
+    data[0] = (data.len() - 1) as u8;
+
+    let (first_block, other_blocks) = data.split_at(ffa::FIRST_BLOCK_DATA_LENGTH);
+    let block_count = 1 + other_blocks.chunks(block_size).len();
+    if writer.remaining_mut() < data.len() + 2 * block_count {
+        return Err(WriteError::BufferTooSmall);
+    }
+
+    for block in [first_block]
+        .into_iter()
+        .chain(other_blocks.chunks(block_size))
+    {
+        writer.put_slice(block);
+        writer.put_u16(CRC.checksum(block));
+    }
+
+    Ok(())
+}
+
+/// Write the data, including a dummy L field, as a frame format B frame with a CRC after each block
+fn write_ffb(writer: &mut impl BufMut, data: &mut [u8]) -> Result<(), WriteError> {
+    let len = data.len();
+
+    // Write L field
+    data[0] = if len <= ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH {
+        len + 2 - 1
+    } else {
+        len + 2 + 2 - 1
+    } as u8;
+
+    let frame_length = data[0] as usize + 1;
+    if writer.remaining_mut() < frame_length {
+        return Err(WriteError::BufferTooSmall);
+    }
+
+    // The first and second block share a single CRC
+    let first_len = len.min(ffb::FIRST_BLOCK_DATA_LENGTH + ffb::SECOND_BLOCK_MAX_DATA_LENGTH);
+    let (first_blocks, optional_block) = data.split_at(first_len);
+    for block in [first_blocks, optional_block] {
+        if !block.is_empty() {
+            writer.put_slice(block);
+            writer.put_u16(CRC.checksum(block));
+        }
+    }
+
+    Ok(())
+}
+
+/// How block CRC's are handled when they are removed from a frame
+pub(crate) enum Verify<'a> {
+    /// Do not verify the CRC's
+    Skip,
+    /// Fail on the first invalid CRC
+    Abort,
+    /// Collect the indices of the blocks with an invalid CRC
+    Collect(&'a mut FailedBlocks),
+    /// Stop before the first block with an invalid CRC
+    Truncate,
+}
+
+impl Verify<'_> {
+    /// Check the CRC of a block, and get whether the block should be included
+    fn check(&mut self, index: usize, block: &[u8]) -> Result<bool, Error> {
+        match self {
+            Verify::Skip => Ok(true),
+            Verify::Abort if !is_valid_crc(block) => Err(Error::Crc(index)),
+            Verify::Abort => Ok(true),
+            Verify::Collect(failed) => {
+                if !is_valid_crc(block) {
+                    // There cannot be more failed blocks than blocks for the smallest block size
+                    failed.push(index).unwrap();
+                }
+                Ok(true)
+            }
+            Verify::Truncate => Ok(is_valid_crc(block)),
+        }
     }
 }
 
@@ -274,6 +730,13 @@ pub(crate) fn is_valid_crc(block: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn can_get_modet_encoded_bits() {
+        assert_eq!(Ok(12 * 12), modet_encoded_bits(12));
+        assert_eq!(Ok(12 * FRAME_MAX), modet_encoded_bits(FRAME_MAX));
+        assert_eq!(Err(Error::InvalidLength), modet_encoded_bits(FRAME_MAX + 1));
+    }
+
     #[test]
     fn can_derive_frame_length() {
         assert_eq!(
@@ -351,4 +814,143 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn can_derive_frame_length_with_policy() {
+        // This is valid 3oo6 but the CRC of the first block is invalid
+        let frame = [
+            0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44,
+            0xdc, 0x5b, 0x44, 0xdc,
+        ];
+
+        assert_eq!(
+            FrameMetadata {
+                mode: Mode::ModeCFFB,
+                frame_offset: 0,
+                frame_length: 1 + 0x5b
+            },
+            FrameMetadata::read_with_policy(&frame, DetectionPolicy::RequireCrc).unwrap()
+        );
+        assert_eq!(
+            FrameMetadata {
+                mode: Mode::ModeTMTO,
+                frame_offset: 0,
+                frame_length: 10 + 2 + 3 + 2
+            },
+            FrameMetadata::read_with_policy(&frame, DetectionPolicy::PreferModeT).unwrap()
+        );
+    }
+
+    #[test]
+    fn derive_frame_length_min() {
+        assert_eq!(3, Mode::ModeCFFA.derive_length_min());
+        assert_eq!(3, Mode::ModeCFFB.derive_length_min());
+        assert_eq!(18, Mode::ModeTMTO.derive_length_min());
+        assert_eq!(3, Mode::ModeTMTODecoded.derive_length_min());
+
+        for mode in Mode::all() {
+            assert_eq!(
+                Err(Error::Incomplete),
+                FrameMetadata::read(&[0x54, 0xCD][..mode.derive_length_min().min(2)])
+            );
+        }
+
+        // This is valid 3oo6 and could also be Mode C FFB
+        let frame = [
+            0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44, 0xdc, 0x5b, 0x44,
+            0xdc, 0x69, 0xcc, 0x99,
+        ];
+        let min = Mode::ModeTMTO.derive_length_min();
+        assert_eq!(
+            Err(Error::Incomplete),
+            FrameMetadata::read(&frame[..min - 1])
+        );
+        assert_eq!(
+            Mode::ModeTMTO,
+            FrameMetadata::read(&frame[..min]).unwrap().mode
+        );
+    }
+
+    #[test]
+    fn can_derive_frame_length_skipping_prefix() {
+        assert_eq!(
+            FrameMetadata {
+                mode: Mode::ModeCFFB,
+                frame_offset: 1 + 2,
+                frame_length: 1 + 0x4E
+            },
+            FrameMetadata::read_skipping(&[0xC8, 0x54, 0x3D, 0x4E], 1).unwrap()
+        );
+        assert_eq!(
+            FrameMetadata {
+                mode: Mode::ModeCFFB,
+                frame_offset: 1,
+                frame_length: 1 + 0x4E
+            },
+            FrameMetadata::read_skipping(&[0xC8, 0x4E, 0x44, 0x00], 1).unwrap()
+        );
+        assert_eq!(
+            Err(Error::Incomplete),
+            FrameMetadata::read_skipping(&[0xC8, 0x54, 0x3D], 1)
+        );
+    }
+
+    /// Test vectors for the EN13757 CRC, so that a change to the CRC parameters is caught
+    mod crc_vectors {
+        use super::*;
+
+        fn checksum(data: &[u8]) -> u16 {
+            CRC.checksum(data)
+        }
+
+        #[test]
+        fn has_en13757_parameters() {
+            let algorithm = CRC.algorithm;
+            assert_eq!(16, algorithm.width);
+            assert_eq!(0x3D65, algorithm.poly);
+            assert_eq!(0x0000, algorithm.init);
+            assert!(!algorithm.refin);
+            assert!(!algorithm.refout);
+            assert_eq!(0xFFFF, algorithm.xorout);
+        }
+
+        #[test]
+        fn matches_check_value() {
+            assert_eq!(0xC2B7, checksum(b"123456789"));
+        }
+
+        #[test]
+        fn matches_empty_input() {
+            // The zero init value is inverted by the final XOR
+            assert_eq!(0xFFFF, checksum(&[]));
+            assert_eq!(0xFFFF, checksum(&[0x00]));
+        }
+
+        #[test]
+        fn matches_meter_blocks() {
+            // The first and second block of a frame format A frame received from a Kamstrup meter
+            assert_eq!(
+                0x9153,
+                checksum(&[0x4E, 0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04])
+            );
+            #[rustfmt::skip]
+            assert_eq!(
+                0xA104,
+                checksum(&[
+                    0x7A, 0xA6, 0x10, 0x40, 0x25, 0x6D, 0x3C, 0xA0,
+                    0xF7, 0x2F, 0xF1, 0xEF, 0x06, 0x80, 0x6C, 0x50,
+                ])
+            );
+        }
+
+        #[test]
+        fn matches_residue() {
+            // A block followed by its big endian CRC yields the residue 0xA366 before the final XOR
+            let block = [
+                0x4E, 0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04, 0x91, 0x53,
+            ];
+            assert!(is_valid_crc(&block));
+            assert_eq!(0xA366 ^ 0xFFFF, checksum(&block));
+        }
+    }
 }
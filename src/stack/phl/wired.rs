@@ -0,0 +1,109 @@
+use bytes::BufMut;
+
+use crate::stack::WriteError;
+
+use super::Error;
+
+/// The start byte of a wired M-Bus long frame
+const LONG_FRAME_START: u8 = 0x68;
+/// The stop byte of a wired M-Bus frame
+const STOP: u8 = 0x16;
+
+/// Compute the wired M-Bus (EN13757-2) checksum, i.e. the arithmetic sum of the bytes modulo 256
+pub fn mbus_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Write `data`, i.e. the C field, the A field, the CI field and any user data, as a wired M-Bus long frame.
+/// The frame is the start sequence `68 L L 68`, the data, the checksum of the data, and the `16` stop byte.
+pub fn write_wired(writer: &mut impl BufMut, data: &[u8]) -> Result<(), WriteError> {
+    if data.len() > u8::MAX as usize {
+        return Err(WriteError::DataTooLong);
+    }
+    if writer.remaining_mut() < 4 + data.len() + 2 {
+        return Err(WriteError::BufferTooSmall);
+    }
+
+    let len = data.len() as u8;
+    writer.put_slice(&[LONG_FRAME_START, len, len, LONG_FRAME_START]);
+    writer.put_slice(data);
+    writer.put_u8(mbus_checksum(data));
+    writer.put_u8(STOP);
+    Ok(())
+}
+
+/// Read a wired M-Bus long frame and return its data, i.e. the C field, the A field, the CI field and any user data.
+/// The start sequence, the checksum and the stop byte are validated.
+pub fn read_wired(frame: &[u8]) -> Result<&[u8], Error> {
+    if frame.len() < 4 {
+        return Err(Error::Incomplete);
+    }
+    if frame[0] != LONG_FRAME_START || frame[3] != LONG_FRAME_START || frame[1] != frame[2] {
+        return Err(Error::Syncword);
+    }
+
+    let len = frame[1] as usize;
+    if frame.len() < 4 + len + 2 {
+        return Err(Error::Incomplete);
+    }
+    if frame[4 + len + 1] != STOP {
+        return Err(Error::InvalidLength);
+    }
+
+    let data = &frame[4..4 + len];
+    if mbus_checksum(data) != frame[4 + len] {
+        return Err(Error::Checksum);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_compute_checksum() {
+        // SND_NKE short frame to primary address 1
+        assert_eq!(0x41, mbus_checksum(&[0x40, 0x01]));
+        assert_eq!(0x00, mbus_checksum(&[]));
+    }
+
+    #[test]
+    fn can_write_wired() {
+        // Set baudrate to 2400 baud for all slaves
+        let mut frame = [0; 16];
+        let mut writer = &mut frame[..];
+        write_wired(&mut writer, &[0x53, 0xFE, 0xBD]).unwrap();
+        let written = 16 - writer.len();
+
+        assert_eq!(
+            [0x68, 0x03, 0x03, 0x68, 0x53, 0xFE, 0xBD, 0x0E, 0x16],
+            frame[..written]
+        );
+    }
+
+    #[test]
+    fn rejects_too_long_wired_data() {
+        let mut frame = [0; 4 + 256 + 2];
+        let mut writer = &mut frame[..];
+        assert_eq!(
+            Err(WriteError::DataTooLong),
+            write_wired(&mut writer, &[0x55; 256])
+        );
+
+        let mut writer = &mut frame[..];
+        assert_eq!(Ok(()), write_wired(&mut writer, &[0x55; 255]));
+        assert_eq!(1, writer.len());
+    }
+
+    #[test]
+    fn can_read_wired() {
+        let frame = [0x68, 0x03, 0x03, 0x68, 0x53, 0xFE, 0xBD, 0x0E, 0x16];
+        assert_eq!(Ok([0x53, 0xFE, 0xBD].as_slice()), read_wired(&frame));
+
+        let mut corrupted = frame;
+        corrupted[5] = 0xFF;
+        assert_eq!(Err(Error::Checksum), read_wired(&corrupted));
+        assert_eq!(Err(Error::Incomplete), read_wired(&frame[..8]));
+    }
+}
@@ -0,0 +1,283 @@
+use bytes::BufMut;
+
+use super::{apl, Layer, Packet, ReadError, WriteError};
+use crate::address::WMBusAddress;
+
+/// Transport Layer.
+/// The CI field and the transport layer header are removed, so the application layer is the data following the header.
+pub struct Tpl<A: Layer> {
+    above: A,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TplFields {
+    /// The CI field without a transport layer header, i.e. 0x78
+    NoHeader,
+    Short {
+        acc: u8,
+        sts: u8,
+        cw: u16,
+    },
+    Long {
+        address: WMBusAddress,
+        acc: u8,
+        sts: u8,
+        cw: u16,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    Incomplete,
+    BcdConversion,
+}
+
+impl From<Error> for ReadError {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Incomplete => ReadError::Incomplete,
+            e => ReadError::Tpl(e),
+        }
+    }
+}
+
+impl<A: Layer> Tpl<A> {
+    pub const fn new(above: A) -> Self {
+        Self { above }
+    }
+}
+
+impl TplFields {
+    pub const fn ci(&self) -> u8 {
+        match self {
+            TplFields::NoHeader => 0x78,
+            TplFields::Short { .. } => 0x7A,
+            TplFields::Long { .. } => 0x72,
+        }
+    }
+
+    /// Get the access number, if there is a header
+    pub const fn acc(&self) -> Option<u8> {
+        match self {
+            TplFields::NoHeader => None,
+            TplFields::Short { acc, .. } | TplFields::Long { acc, .. } => Some(*acc),
+        }
+    }
+
+    /// Get the status byte, if there is a header
+    pub const fn sts(&self) -> Option<u8> {
+        match self {
+            TplFields::NoHeader => None,
+            TplFields::Short { sts, .. } | TplFields::Long { sts, .. } => Some(*sts),
+        }
+    }
+
+    /// Get the configuration word, if there is a header
+    pub const fn cw(&self) -> Option<u16> {
+        match self {
+            TplFields::NoHeader => None,
+            TplFields::Short { cw, .. } | TplFields::Long { cw, .. } => Some(*cw),
+        }
+    }
+
+    /// Get the meter address of a long header, if any
+    pub const fn address(&self) -> Option<&WMBusAddress> {
+        match self {
+            TplFields::Long { address, .. } => Some(address),
+            TplFields::NoHeader | TplFields::Short { .. } => None,
+        }
+    }
+}
+
+impl<A: Layer> Layer for Tpl<A> {
+    fn read<const N: usize>(&self, packet: &mut Packet<N>, buffer: &[u8]) -> Result<(), ReadError> {
+        let mut offset = 0;
+        if let Some(header_length) = buffer.first().and_then(|&ci| header_length(ci)) {
+            if buffer.len() < header_length {
+                return Err(Error::Incomplete)?;
+            }
+            packet.tpl = Some(match buffer[0] {
+                0x78 => TplFields::NoHeader,
+                0x7A => TplFields::Short {
+                    acc: buffer[1],
+                    sts: buffer[2],
+                    cw: u16::from_le_bytes([buffer[3], buffer[4]]),
+                },
+                _ => TplFields::Long {
+                    address: apl::long_header_address(buffer[0], &buffer[1..])
+                        .ok_or(Error::BcdConversion)?,
+                    acc: buffer[9],
+                    sts: buffer[10],
+                    cw: u16::from_le_bytes([buffer[11], buffer[12]]),
+                },
+            });
+
+            offset = header_length;
+        }
+
+        self.above.read(packet, &buffer[offset..])
+    }
+
+    fn write<const N: usize>(
+        &self,
+        writer: &mut impl BufMut,
+        packet: &Packet<N>,
+    ) -> Result<(), WriteError> {
+        if let Some(fields) = &packet.tpl {
+            if writer.remaining_mut() < header_length(fields.ci()).unwrap() {
+                return Err(WriteError::BufferTooSmall);
+            }
+
+            writer.put_u8(fields.ci());
+            if let Some(address) = fields.address() {
+                // The serial number comes first, unlike in the data link layer address
+                let bytes = address.get_bytes();
+                writer.put_slice(&bytes[2..6]);
+                writer.put_slice(&bytes[..2]);
+                writer.put_slice(&bytes[6..]);
+            }
+            if let (Some(acc), Some(sts), Some(cw)) = (fields.acc(), fields.sts(), fields.cw()) {
+                writer.put_u8(acc);
+                writer.put_u8(sts);
+                writer.put_u16_le(cw);
+            }
+        }
+        self.above.write(writer, packet)?;
+        Ok(())
+    }
+}
+
+pub(crate) const fn header_length(ci: u8) -> Option<usize> {
+    match ci {
+        0x78 => Some(1),
+        0x7A => Some(1 + 4),
+        0x72 => Some(1 + 12),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{
+        stack::{apl::Apl, Mode},
+        DeviceType, ManufacturerCode,
+    };
+
+    use super::*;
+
+    #[test]
+    fn can_read_short_header() {
+        // Given
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        let buffer = [0x7A, 0x01, 0x02, 0x10, 0x05, 0x04, 0x13];
+
+        // When
+        tpl.read(&mut packet, &buffer).unwrap();
+
+        // Then
+        let fields = packet.tpl.unwrap();
+        assert_eq!(Some(0x01), fields.acc());
+        assert_eq!(Some(0x02), fields.sts());
+        assert_eq!(Some(0x0510), fields.cw());
+        assert!(fields.address().is_none());
+        assert_eq!(&[0x04, 0x13], packet.apl.as_slice());
+    }
+
+    #[test]
+    fn can_read_long_header() {
+        // Given
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        #[rustfmt::skip]
+        let buffer = [
+            0x72, 0x78, 0x56, 0x34, 0x12, 0x2D, 0x2C, 0x01, 0x04, 0x01, 0x02, 0x00, 0x00,
+            0x04, 0x13,
+        ];
+
+        // When
+        tpl.read(&mut packet, &buffer).unwrap();
+
+        // Then
+        let fields = packet.tpl.unwrap();
+        assert_eq!(
+            Some(&WMBusAddress::new(
+                ManufacturerCode::KAM,
+                12345678,
+                0x01,
+                DeviceType::Heat
+            )),
+            fields.address()
+        );
+        assert_eq!(Some(0x01), fields.acc());
+        assert_eq!(Some(0x02), fields.sts());
+        assert_eq!(Some(0x0000), fields.cw());
+        assert_eq!(&[0x04, 0x13], packet.apl.as_slice());
+    }
+
+    #[test]
+    fn can_read_no_header() {
+        // Given
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        let buffer = [0x78, 0x04, 0x13];
+
+        // When
+        tpl.read(&mut packet, &buffer).unwrap();
+
+        // Then
+        let fields = packet.tpl.as_ref().unwrap();
+        assert!(TplFields::NoHeader == *fields);
+        assert_eq!(None, fields.acc());
+        assert_eq!(&[0x04, 0x13], packet.apl.as_slice());
+
+        let mut writer = BytesMut::new();
+        tpl.write(&mut writer, &packet).unwrap();
+        assert_eq!(&buffer, writer.as_ref());
+    }
+
+    #[test]
+    fn ignores_other_ci() {
+        // Given
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        let buffer = [0x79, 0x04, 0x13];
+
+        // When
+        tpl.read(&mut packet, &buffer).unwrap();
+
+        // Then
+        assert!(packet.tpl.is_none());
+        assert_eq!(&buffer, packet.apl.as_slice());
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        assert_eq!(
+            Err(ReadError::Incomplete),
+            tpl.read(&mut packet, &[0x72, 0x78, 0x56, 0x34, 0x12])
+        );
+    }
+
+    #[test]
+    fn can_write_long_header() {
+        let tpl = Tpl::new(Apl::with_min_data_lengths(&[]));
+        #[rustfmt::skip]
+        let buffer = [
+            0x72, 0x78, 0x56, 0x34, 0x12, 0x2D, 0x2C, 0x01, 0x04, 0x01, 0x02, 0x00, 0x00,
+            0x04, 0x13,
+        ];
+        let mut packet: Packet = Packet::new(Mode::ModeCFFB);
+        tpl.read(&mut packet, &buffer).unwrap();
+
+        let mut writer = BytesMut::new();
+        tpl.write(&mut writer, &packet).unwrap();
+
+        assert_eq!(&buffer, writer.as_ref());
+    }
+}
@@ -1,12 +1,16 @@
 use assert_hex::assert_eq_hex;
+use core::cell::Cell;
+
 use bitvec::prelude::*;
 use wmbus::{
     modet::threeoutofsix::ThreeOutOfSix,
     stack::{
-        phl::{FrameFormat, FFA, FFB},
-        Mode, Stack,
+        apl::Apl,
+        dll::{Dll, DllFields},
+        phl::{self, FrameFormat, Phl, FFA, FFB},
+        Mode, Packet, ReadError, Stack,
     },
-    DeviceType, ManufacturerCode,
+    DeviceType, ManufacturerCode, WMBusAddress,
 };
 
 #[test]
@@ -30,10 +34,7 @@ fn can_read_modec_ffa() {
     assert_eq!(frame.len(), FFA::get_frame_length(frame).unwrap());
 
     let dll = packet.dll.unwrap();
-    assert_eq!(
-        ManufacturerCode::KAM,
-        dll.address.manufacturer_code().unwrap()
-    );
+    assert_eq!(ManufacturerCode::KAM, dll.address.manufacturer_code());
     assert_eq!(67042798, dll.address.serial_number());
     assert_eq_hex!(0x30, dll.address.version());
     assert_eq!(DeviceType::Heat, dll.address.device_type().unwrap());
@@ -63,10 +64,7 @@ fn can_read_modec_ffb() {
     assert_eq!(frame.len(), FFB::get_frame_length(frame).unwrap());
 
     let dll = packet.dll.unwrap();
-    assert_eq!(
-        ManufacturerCode::KAM,
-        dll.address.manufacturer_code().unwrap()
-    );
+    assert_eq!(ManufacturerCode::KAM, dll.address.manufacturer_code());
     assert_eq!(12345678, dll.address.serial_number());
     assert_eq_hex!(0x01, dll.address.version());
     assert_eq!(DeviceType::Repeater, dll.address.device_type().unwrap());
@@ -79,6 +77,27 @@ fn can_read_modec_ffb() {
     assert_eq_hex!(0x06, *apl.last().unwrap());
 }
 
+#[test]
+fn can_read_manufacturer_specific_ci() {
+    // Given
+    let stack = Stack::new();
+    // The proprietary data resembles an ELL and an AFL header
+    #[rustfmt::skip]
+    let frame = &[
+        0x14, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x07,
+        0xA0, 0x8C, 0x00, 0x01, 0x90, 0x02, 0x7A, 0x01, 0x02, 0x19, 0x7F,
+    ];
+
+    // When
+    let packet = stack.read(frame, Mode::ModeCFFB).unwrap();
+
+    // Then
+    assert!(packet.ell.is_none());
+    assert_eq!(&frame[10..19], packet.apl.as_slice());
+    assert_eq!(Some((0xA0, &frame[11..19])), packet.application());
+    assert!(packet.data_records().is_none());
+}
+
 #[test]
 fn can_read_modec_ffb_presync() {
     // Given
@@ -95,10 +114,7 @@ fn can_read_modec_ffb_presync() {
 
     // Then
     let dll = packet.dll.unwrap();
-    assert_eq!(
-        ManufacturerCode::KAM,
-        dll.address.manufacturer_code().unwrap()
-    );
+    assert_eq!(ManufacturerCode::KAM, dll.address.manufacturer_code());
     assert_eq!(12345678, dll.address.serial_number());
     assert_eq_hex!(0x01, dll.address.version());
     assert_eq!(DeviceType::Repeater, dll.address.device_type().unwrap());
@@ -111,6 +127,68 @@ fn can_read_modec_ffb_presync() {
     assert_eq_hex!(0x06, *apl.last().unwrap());
 }
 
+#[test]
+fn can_read_modec_with_trailing_padding() {
+    // Given
+    let stack = Stack::new();
+    // Fixed length radios pad the frame with zeros
+    #[rustfmt::skip]
+    let ffa = &[
+        0x0A, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xBA, 0x10,
+        0xA0, 0x9B, 0x08,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    #[rustfmt::skip]
+    let ffb = &[
+        0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32,
+        0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xC3, 0xC0,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // When
+    let ffa_packet = stack.read(ffa, Mode::ModeCFFA).unwrap();
+    let ffb_packet = stack.read(ffb, Mode::ModeCFFB).unwrap();
+
+    // Then
+    assert_eq!(ffa.len() - 10, FFA::get_frame_length(ffa).unwrap());
+    assert_eq!(&[0xA0], ffa_packet.apl.as_slice());
+    assert_eq!(ffb.len() - 10, FFB::get_frame_length(ffb).unwrap());
+    assert_eq!(&ffb[10..18], ffb_packet.apl.as_slice());
+}
+
+#[test]
+fn can_read_modes() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x76, 0x96,
+        0x4E, 0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04, 0x91, 0x53,
+        0x7A, 0xA6, 0x10, 0x40, 0x25, 0x6D, 0x3C, 0xA0, 0xF7, 0x2F, 0xF1, 0xEF, 0x06, 0x80, 0x6C, 0x50, 0xA1, 0x04,
+        0x21, 0xCB, 0xD1, 0x32, 0xE3, 0xB1, 0xD0, 0x11, 0x6A, 0x05, 0x57, 0x69, 0x6E, 0x0E, 0x37, 0xC2, 0xE9, 0xF0,
+        0x86, 0x36, 0xFE, 0x31, 0xF6, 0x8E, 0x6B, 0x4D, 0xEE, 0x5E, 0x38, 0x53, 0x16, 0xC2, 0x16, 0xA9, 0x6E, 0x27,
+        0x7D, 0x48, 0xB1, 0x45, 0x92, 0x72, 0x38, 0x61, 0x46, 0xF7, 0x8C, 0x77, 0x66, 0xD5, 0x19, 0xFC, 0x44, 0x49,
+        0x99, 0x3A, 0xDA, 0x5A, 0xAD, 0x95, 0xA5,
+    ];
+
+    // When
+    let metadata = phl::FrameMetadata::read(frame).unwrap();
+    let packet = stack.read(frame, metadata.mode).unwrap();
+
+    // Then
+    assert_eq!(Mode::ModeS, metadata.mode);
+    assert_eq!(2, metadata.frame_offset);
+    assert_eq!(frame.len() - 2, metadata.frame_length);
+
+    let dll = packet.dll.unwrap();
+    assert_eq!(67042798, dll.address.serial_number());
+    assert_eq!(69, packet.apl.len());
+    assert_eq!(
+        packet.apl,
+        stack.read(&frame[2..], Mode::ModeS).unwrap().apl
+    );
+}
+
 #[test]
 fn can_read_modet() {
     // Given
@@ -136,10 +214,7 @@ fn can_read_modet() {
     assert_eq!(frame.len(), FFA::get_frame_length(frame).unwrap());
 
     let dll = packet.dll.unwrap();
-    assert_eq!(
-        ManufacturerCode::KAM,
-        dll.address.manufacturer_code().unwrap()
-    );
+    assert_eq!(ManufacturerCode::KAM, dll.address.manufacturer_code());
     assert_eq!(67042798, dll.address.serial_number());
     assert_eq_hex!(0x30, dll.address.version());
     assert_eq!(DeviceType::Heat, dll.address.device_type().unwrap());
@@ -151,3 +226,217 @@ fn can_read_modet() {
     assert_eq_hex!(0x7A, apl[0]);
     assert_eq_hex!(0xAD, *apl.last().unwrap());
 }
+
+#[test]
+fn can_write_modet() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x4E, 0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04, 0x91, 0x53,
+        0x7A, 0xA6, 0x10, 0x40, 0x25, 0x6D, 0x3C, 0xA0, 0xF7, 0x2F, 0xF1, 0xEF, 0x06, 0x80, 0x6C, 0x50, 0xA1, 0x04,
+        0x21, 0xCB, 0xD1, 0x32, 0xE3, 0xB1, 0xD0, 0x11, 0x6A, 0x05, 0x57, 0x69, 0x6E, 0x0E, 0x37, 0xC2, 0xE9, 0xF0,
+        0x86, 0x36, 0xFE, 0x31, 0xF6, 0x8E, 0x6B, 0x4D, 0xEE, 0x5E, 0x38, 0x53, 0x16, 0xC2, 0x16, 0xA9, 0x6E, 0x27,
+        0x7D, 0x48, 0xB1, 0x45, 0x92, 0x72, 0x38, 0x61, 0x46, 0xF7, 0x8C, 0x77, 0x66, 0xD5, 0x19, 0xFC, 0x44, 0x49,
+        0x99, 0x3A, 0xDA, 0x5A, 0xAD, 0x95, 0xA5,
+    ];
+    let mut encode_buf = bitarr![u8, Msb0; 0; 91 * 2 * 6];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    // The frame has an odd number of bytes, so the last byte is padded with a zero nibble
+    let encoded_bytes = encoded_bits.div_ceil(8);
+    let encoded = &encode_buf.as_raw_slice()[..encoded_bytes];
+    let mut packet = stack.read(frame, Mode::ModeCFFA).unwrap();
+    packet.mode = Mode::ModeTMTO;
+
+    // When
+    let mut out = [0; 256];
+    let written = stack.write_to_slice(&mut out, &packet).unwrap();
+
+    // Then
+    assert_eq!(137, written);
+    assert_eq_hex!(encoded, &out[..written]);
+
+    let written_packet = stack.read(&out[..written], Mode::ModeTMTO).unwrap();
+    assert_eq!(packet.apl, written_packet.apl);
+}
+
+#[test]
+fn can_read_modet_minimum_length() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x0A, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xBA, 0x10,
+        0x78, 0x9B, 0xE0,
+    ];
+    let mut encode_buf = bitarr![u8, Msb0; 0; 15 * 2 * 6];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    let encoded_bytes = encoded_bits.div_ceil(8); // Round up to nearest byte boundary
+    let encoded = &encode_buf.as_raw_slice()[..encoded_bytes];
+
+    // When
+    let packet = stack.read(encoded, Mode::ModeTMTO).unwrap();
+
+    // Then
+    assert_eq!(frame.len(), FFA::get_frame_length(frame).unwrap());
+
+    let dll = packet.dll.unwrap();
+    assert_eq!(12345678, dll.address.serial_number());
+
+    let apl = packet.apl;
+    assert_eq!(1, apl.len());
+    assert_eq_hex!(0x78, apl[0]);
+}
+
+#[test]
+fn rejects_modet_with_invalid_length() {
+    // Given
+    // A manufacturer specific block size of 1 byte
+    let mut phl = Phl::new(Dll::new(Apl::new()));
+    phl.set_ffa_block_size(1);
+    let stack = Stack::from_phl(phl);
+    let frame = &[
+        0xFF, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0xBA, 0x10, 0x78, 0x9B, 0xE0,
+    ];
+    let mut encode_buf = bitarr![u8, Msb0; 0; 15 * 2 * 6];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    let encoded = &encode_buf.as_raw_slice()[..encoded_bits / 8];
+
+    // When
+    // The L field announces a frame with more blocks than can be received
+    let result = stack.read(encoded, Mode::ModeTMTO);
+
+    // Then
+    assert_eq!(
+        Err(ReadError::Phl(phl::Error::InvalidLength)),
+        result.map(|_| ())
+    );
+}
+
+#[test]
+fn can_read_modet_decoded() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0x9E, 0xE1,
+        0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x7A, 0x24,
+    ];
+    let mut encode_buf = bitarr![u8, Msb0; 0; 24 * 2 * 6];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    let encoded = &encode_buf.as_raw_slice()[..encoded_bits / 8];
+
+    // When
+    let packet = stack.read(frame, Mode::ModeTMTODecoded).unwrap();
+
+    // Then
+    let encoded_packet = stack.read(encoded, Mode::ModeTMTO).unwrap();
+    assert_eq!(
+        encoded_packet.dll.unwrap().address,
+        packet.dll.unwrap().address
+    );
+    assert_eq!(encoded_packet.apl, packet.apl);
+    assert_eq!(
+        Err(ReadError::Phl(phl::Error::ThreeOutOfSix(
+            wmbus::modet::threeoutofsix::Error::Symbol(0)
+        ))),
+        stack.read(frame, Mode::ModeTMTO).map(|_| ())
+    );
+}
+
+#[test]
+fn can_read_modet_with_trailing_padding() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x13, 0x44, 0x2D, 0x2C, 0x78, 0x56, 0x34, 0x12, 0x01, 0x32, 0x9E, 0xE1,
+        0xA0, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x7A, 0x24,
+    ];
+    let mut encode_buf = bitarr![u8, Msb0; 0; 24 * 2 * 6 + 5 * 8];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    // Pad with filler symbols that are not valid 3oo6
+    encode_buf[encoded_bits..].fill(true);
+    let encoded = encode_buf.as_raw_slice();
+
+    // When
+    let packet = stack.read(encoded, Mode::ModeTMTO).unwrap();
+
+    // Then
+    let apl = packet.apl;
+    assert_eq!(10, apl.len());
+    assert_eq_hex!(0xA0, apl[0]);
+    assert_eq_hex!(0x08, *apl.last().unwrap());
+}
+
+#[test]
+fn modet_crc_is_computed_over_decoded_bytes() {
+    // Given
+    let stack = Stack::new();
+    #[rustfmt::skip]
+    let frame = &[
+        0x4E, 0x44, 0x2D, 0x2C, 0x98, 0x27, 0x04, 0x67, 0x30, 0x04, 0x91, 0x53,
+        0x7A, 0xA6, 0x10, 0x40, 0x25, 0x6D, 0x3C, 0xA0, 0xF7, 0x2F, 0xF1, 0xEF, 0x06, 0x80, 0x6C, 0x50, 0xA1, 0x04,
+        0x21, 0xCB, 0xD1, 0x32, 0xE3, 0xB1, 0xD0, 0x11, 0x6A, 0x05, 0x57, 0x69, 0x6E, 0x0E, 0x37, 0xC2, 0xE9, 0xF0,
+        0x86, 0x36, 0xFE, 0x31, 0xF6, 0x8E, 0x6B, 0x4D, 0xEE, 0x5E, 0x38, 0x53, 0x16, 0xC2, 0x16, 0xA9, 0x6E, 0x27,
+        0x7D, 0x48, 0xB1, 0x45, 0x92, 0x72, 0x38, 0x61, 0x46, 0xF7, 0x8C, 0x77, 0x66, 0xD5, 0x19, 0xFC, 0x44, 0x49,
+        0x99, 0x3A, 0xDA, 0x5A, 0xAD, 0x95, 0xA5,
+    ];
+
+    // Corrupt a data byte in the second block.
+    // The corrupted frame is still valid 3oo6 so only the CRC over the decoded bytes can reject it.
+    let mut corrupted = *frame;
+    corrupted[13] ^= 0x01;
+
+    let mut encode_buf = bitarr![u8, Msb0; 0; 91 * 2 * 6];
+    let encoded_bits = ThreeOutOfSix::encode(&mut encode_buf, frame).unwrap();
+    let encoded_bytes = encoded_bits.div_ceil(8);
+    let encoded = &encode_buf.as_raw_slice()[..encoded_bytes];
+
+    let mut corrupted_encode_buf = bitarr![u8, Msb0; 0; 91 * 2 * 6];
+    ThreeOutOfSix::encode(&mut corrupted_encode_buf, &corrupted).unwrap();
+    let corrupted_encoded = &corrupted_encode_buf.as_raw_slice()[..encoded_bytes];
+
+    // When
+    let packet = stack.read(encoded, Mode::ModeTMTO);
+    let corrupted_packet = stack.read(corrupted_encoded, Mode::ModeTMTO);
+
+    // Then
+    assert!(packet.is_ok());
+    assert_eq!(
+        Err(ReadError::Phl(phl::Error::Crc(1))),
+        corrupted_packet.map(|_| ())
+    );
+}
+
+#[test]
+fn can_stream_records_in_stack() {
+    // Given
+    let mut packet: Packet = Packet::new(Mode::ModeCFFA);
+    packet.dll = Some(DllFields::new(
+        0x44,
+        WMBusAddress::new(ManufacturerCode::KAM, 12345678, 0x01, DeviceType::Heat),
+    ));
+    #[rustfmt::skip]
+    packet.apl.extend_from_slice(&[
+        0x7A, 0x01, 0x00, 0x00, 0x00,
+        0x04, 0x13, 0x01, 0x00, 0x00, 0x00,
+        0x02, 0x5A, 0x10, 0x00,
+    ]).unwrap();
+    let mut frame = [0; FFA::FRAME_MAX];
+    let frame_length = Stack::without_ell()
+        .write_to_slice(&mut frame, &packet)
+        .unwrap();
+
+    let count = Cell::new(0);
+    let stack = Stack::from_phl(Phl::new(Dll::new(Apl::with_record_sink(|_| {
+        count.set(count.get() + 1)
+    }))));
+
+    // When
+    let packet = stack.read(&frame[..frame_length], Mode::ModeCFFA).unwrap();
+
+    // Then
+    assert_eq!(2, count.get());
+    assert!(packet.apl.is_empty());
+}